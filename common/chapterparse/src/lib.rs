@@ -0,0 +1,208 @@
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref VOLUME: Regex =
+        Regex::new(r"(?i)\b(?:volume|vol|tome)\b\.?\s*(\d+(?:\.\d+)?)").unwrap();
+    static ref CHAPTER: Regex = Regex::new(
+        r"(?i)(?:\b(?:chapter|chap|ch|episode|ep|capitulo|capítulo|cap)\b\.?|#)\s*(\d+(?:[.,]\d+)?)"
+    )
+    .unwrap();
+    static ref SEASON: Regex =
+        Regex::new(r"(?i)\b(?:season\b\.?|s(?=\d))\s*(\d+)\b").unwrap();
+    static ref PART: Regex = Regex::new(r"(?i)\b(?:part|pt)\b\.?\s*(\d+)\b").unwrap();
+    static ref NUMBER: Regex = Regex::new(r"(?<![\w.,])(\d+(?:[.,]\d+)?)(?![\w.,])").unwrap();
+    static ref END_MARKER: Regex =
+        Regex::new(r"(?i)[\[(]\s*(?:end|completed?|final)\s*[\])]").unwrap();
+}
+
+/// Separators sites put between the number and the name of a chapter.
+const SEPARATORS: &[char] = &['-', ':', '–', '—', '.', '|', '~', ','];
+
+/// What a chapter title such as `Vol.3 Chapter 7.5 - The Return` says.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChapterTitle {
+    pub volume: Option<f64>,
+    pub number: Option<f64>,
+    /// The season, as in `Season 2 Ep 10` or `S2 - Chapter 3`.
+    pub season: Option<f64>,
+    /// The part right after the number, as in `Chapter 12 Part 2`.
+    pub part: Option<f64>,
+    /// The name after the number, without markers such as `[END]`.
+    pub name: Option<String>,
+    /// The whole title with its whitespace collapsed, to show as is.
+    pub title: String,
+}
+
+impl ChapterTitle {
+    /// The title to list the chapter under: the site's own title without
+    /// markers such as `[END]`.
+    pub fn clean_title(&self) -> String {
+        collapse_whitespace(&END_MARKER.replace_all(&self.title, ""))
+    }
+}
+
+/// Reads the numbers and name out of a chapter title as sites write it,
+/// such as `Ch. 12.5`, `Episode 40` or `Vol.3 Chapter 7 - Title`.
+///
+/// A bare number is taken as the chapter when no `Chapter`, `Ch.`, `Episode`
+/// or `#` marks one.
+pub fn parse_chapter_title(title: &str) -> ChapterTitle {
+    let title = collapse_whitespace(title);
+
+    let volume = first_number(&VOLUME, &title);
+    let season = first_number(&SEASON, &title);
+
+    // the chapter, and where its name starts
+    let chapter = find(&CHAPTER, &title).or_else(|| bare_number(&title));
+
+    let (number, part, name) = match chapter {
+        Some((number, end)) => {
+            let mut rest = &title[end..];
+            // a part is only the chapter's when it follows the number, in
+            // `Chapter 10 - Part 1 of the war` it belongs to the name
+            let part = leading(&PART, rest).map(|(part, part_end)| {
+                rest = &rest[part_end..];
+                part
+            });
+            let rest = END_MARKER.replace_all(rest, "");
+            let name = rest
+                .trim()
+                .trim_start_matches(|c: char| SEPARATORS.contains(&c) || c.is_whitespace())
+                .trim();
+            (
+                Some(number),
+                part,
+                Some(name.to_string()).filter(|name| !name.is_empty()),
+            )
+        }
+        None => (None, first_number(&PART, &title), None),
+    };
+
+    ChapterTitle {
+        volume,
+        number,
+        season,
+        part,
+        name,
+        title,
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The number captured by `regex` and the end of the whole match.
+fn find(regex: &Regex, text: &str) -> Option<(f64, usize)> {
+    let captures = regex.captures(text).ok()??;
+    let number = captures.get(1)?.as_str().replace(',', ".").parse().ok()?;
+    Some((number, captures.get(0)?.end()))
+}
+
+/// Like [`find`], but only when the match opens `text`.
+fn leading(regex: &Regex, text: &str) -> Option<(f64, usize)> {
+    let captures = regex.captures(text).ok()??;
+    let whole = captures.get(0)?;
+    if !text[..whole.start()].trim().is_empty() {
+        return None;
+    }
+    let number = captures.get(1)?.as_str().parse().ok()?;
+    Some((number, whole.end()))
+}
+
+fn first_number(regex: &Regex, text: &str) -> Option<f64> {
+    find(regex, text).map(|(number, _)| number)
+}
+
+/// The first number that is not the volume, the season or a part.
+fn bare_number(text: &str) -> Option<(f64, usize)> {
+    let taken: Vec<_> = VOLUME
+        .find_iter(text)
+        .chain(SEASON.find_iter(text))
+        .chain(PART.find_iter(text))
+        .filter_map(|m| m.ok())
+        .map(|m| m.range())
+        .collect();
+
+    NUMBER
+        .find_iter(text)
+        .filter_map(|m| m.ok())
+        .find(|m| !taken.iter().any(|range| range.contains(&m.start())))
+        .and_then(|m| Some((m.as_str().replace(',', ".").parse().ok()?, m.end())))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Title, volume, number, season, part and name.
+    type Case = (
+        &'static str,
+        Option<f64>,
+        Option<f64>,
+        Option<f64>,
+        Option<f64>,
+        Option<&'static str>,
+    );
+
+    #[test]
+    fn test_parse_chapter_title() {
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            ("Chapter 12", None, Some(12.0), None, None, None),
+            ("Ch. 12.5", None, Some(12.5), None, None, None),
+            ("Episode 40", None, Some(40.0), None, None, None),
+            ("Vol.3 Chapter 7 - Title", Some(3.0), Some(7.0), None, None, Some("Title")),
+            ("Season 2 Ep 10", None, Some(10.0), Some(2.0), None, None),
+            ("S2 - Chapter 3", None, Some(3.0), Some(2.0), None, None),
+            ("Chapter 100 [END]", None, Some(100.0), None, None, None),
+            ("Chapter 5: The 2nd Coming", None, Some(5.0), None, None, Some("The 2nd Coming")),
+            ("Chapter 12 Part 2 - Reunion", None, Some(12.0), None, Some(2.0), Some("Reunion")),
+            ("Chapter 10 - Part 1 of the war", None, Some(10.0), None, None, Some("Part 1 of the war")),
+            ("Volume 2  Chapter 13.1", Some(2.0), Some(13.1), None, None, None),
+            ("Capítulo 8,5", None, Some(8.5), None, None, None),
+            ("#41 A New Day", None, Some(41.0), None, None, Some("A New Day")),
+            ("Solo Leveling 110", None, Some(110.0), None, None, None),
+            ("S2 - 10 Beginnings", None, Some(10.0), Some(2.0), None, Some("Beginnings")),
+            ("Oneshot", None, None, None, None, None),
+        ];
+
+        for (title, volume, number, season, part, name) in cases {
+            let parsed = parse_chapter_title(title);
+            assert_eq!(parsed.volume, *volume, "volume of {:?}", title);
+            assert_eq!(parsed.number, *number, "number of {:?}", title);
+            assert_eq!(parsed.season, *season, "season of {:?}", title);
+            assert_eq!(parsed.part, *part, "part of {:?}", title);
+            assert_eq!(parsed.name.as_deref(), *name, "name of {:?}", title);
+        }
+    }
+
+    #[test]
+    fn test_clean_title() {
+        let cases = [
+            ("Chapter 100 [END]", "Chapter 100"),
+            ("Ch. 12.5", "Ch. 12.5"),
+            ("Vol.3 Chapter 7 - Title", "Vol.3 Chapter 7 - Title"),
+            ("Chapter 12 Part 2 - Reunion", "Chapter 12 Part 2 - Reunion"),
+            ("Oneshot (Completed)", "Oneshot"),
+            ("Season 2 Ep 10", "Season 2 Ep 10"),
+            ("S2 - Chapter 3", "S2 - Chapter 3"),
+            ("Chapter 10 - Part 1 of the war", "Chapter 10 - Part 1 of the war"),
+            ("Solo Leveling 110", "Solo Leveling 110"),
+            ("Episode 40", "Episode 40"),
+            ("Chapter 99 [END]  - Goodbye", "Chapter 99 - Goodbye"),
+        ];
+
+        for (title, clean) in cases {
+            assert_eq!(parse_chapter_title(title).clean_title(), clean, "{:?}", title);
+        }
+    }
+
+    #[test]
+    fn test_title_whitespace_is_collapsed() {
+        let parsed = parse_chapter_title("\n  Chapter  3 \t- Start\n");
+        assert_eq!(parsed.title, "Chapter 3 - Start");
+        assert_eq!(parsed.name.as_deref(), Some("Start"));
+    }
+}
@@ -0,0 +1,624 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+/// Language the dates of a site are written in. English is always tried as
+/// well, since many sites mix it in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    Pt,
+    Fr,
+    De,
+    Id,
+    Tr,
+}
+
+impl Locale {
+    /// The locale of an ISO 639-1 code such as `es` or `pt-BR`, English for
+    /// unknown ones.
+    pub fn from_lang(lang: &str) -> Self {
+        let lang = lang.to_ascii_lowercase();
+        match lang.split(['-', '_']).next().unwrap_or_default() {
+            "es" => Self::Es,
+            "pt" => Self::Pt,
+            "fr" => Self::Fr,
+            "de" => Self::De,
+            "id" => Self::Id,
+            "tr" => Self::Tr,
+            _ => Self::En,
+        }
+    }
+
+    fn words(&self) -> &'static Words {
+        match self {
+            Self::En => &EN,
+            Self::Es => &ES,
+            Self::Pt => &PT,
+            Self::Fr => &FR,
+            Self::De => &DE,
+            Self::Id => &ID,
+            Self::Tr => &TR,
+        }
+    }
+
+    /// Whether `01/02/2022` is the 2nd of January rather than the 1st of
+    /// February.
+    fn month_first(&self) -> bool {
+        matches!(self, Self::En)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    fn duration(&self, amount: i64) -> Duration {
+        match self {
+            Self::Second => Duration::seconds(amount),
+            Self::Minute => Duration::minutes(amount),
+            Self::Hour => Duration::hours(amount),
+            Self::Day => Duration::days(amount),
+            Self::Week => Duration::weeks(amount),
+            Self::Month => Duration::days(30 * amount),
+            Self::Year => Duration::days(365 * amount),
+        }
+    }
+}
+
+/// The words of one language. Units and months match on their prefix, so
+/// `min`, `mins` and `minutes` all need only `min`; stems of one or two
+/// letters have to match exactly.
+struct Words {
+    months: [&'static str; 12],
+    units: &'static [(&'static str, Unit)],
+    /// Words meaning "one", as in "an hour ago".
+    one: &'static [&'static str],
+    ago: &'static [&'static str],
+    now: &'static [&'static str],
+    today: &'static [&'static str],
+    yesterday: &'static [&'static str],
+}
+
+const EN: Words = Words {
+    months: [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ],
+    units: &[
+        ("sec", Unit::Second),
+        ("s", Unit::Second),
+        ("min", Unit::Minute),
+        ("m", Unit::Minute),
+        ("hour", Unit::Hour),
+        ("hr", Unit::Hour),
+        ("h", Unit::Hour),
+        ("day", Unit::Day),
+        ("d", Unit::Day),
+        ("week", Unit::Week),
+        ("wk", Unit::Week),
+        ("w", Unit::Week),
+        ("month", Unit::Month),
+        ("mo", Unit::Month),
+        ("year", Unit::Year),
+        ("yr", Unit::Year),
+        ("y", Unit::Year),
+    ],
+    one: &["a", "an", "one"],
+    ago: &["ago"],
+    now: &["just now", "now", "moments ago", "a moment ago"],
+    today: &["today"],
+    yesterday: &["yesterday"],
+};
+
+const ES: Words = Words {
+    months: [
+        "enero",
+        "febrero",
+        "marzo",
+        "abril",
+        "mayo",
+        "junio",
+        "julio",
+        "agosto",
+        "septiembre",
+        "octubre",
+        "noviembre",
+        "diciembre",
+    ],
+    units: &[
+        ("segundo", Unit::Second),
+        ("minuto", Unit::Minute),
+        ("hora", Unit::Hour),
+        ("día", Unit::Day),
+        ("dia", Unit::Day),
+        ("semana", Unit::Week),
+        ("mes", Unit::Month),
+        ("año", Unit::Year),
+        ("ano", Unit::Year),
+    ],
+    one: &["un", "una"],
+    ago: &["hace"],
+    now: &["justo ahora", "ahora"],
+    today: &["hoy"],
+    yesterday: &["ayer"],
+};
+
+const PT: Words = Words {
+    months: [
+        "janeiro",
+        "fevereiro",
+        "março",
+        "abril",
+        "maio",
+        "junho",
+        "julho",
+        "agosto",
+        "setembro",
+        "outubro",
+        "novembro",
+        "dezembro",
+    ],
+    units: &[
+        ("segundo", Unit::Second),
+        ("minuto", Unit::Minute),
+        ("hora", Unit::Hour),
+        ("dia", Unit::Day),
+        ("semana", Unit::Week),
+        ("mês", Unit::Month),
+        ("mes", Unit::Month),
+        ("ano", Unit::Year),
+    ],
+    one: &["um", "uma"],
+    ago: &["há", "ha", "atrás", "atras"],
+    now: &["agora"],
+    today: &["hoje"],
+    yesterday: &["ontem"],
+};
+
+const FR: Words = Words {
+    months: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    units: &[
+        ("seconde", Unit::Second),
+        ("minute", Unit::Minute),
+        ("heure", Unit::Hour),
+        ("jour", Unit::Day),
+        ("semaine", Unit::Week),
+        ("mois", Unit::Month),
+        ("année", Unit::Year),
+        ("an", Unit::Year),
+        ("ans", Unit::Year),
+    ],
+    one: &["un", "une"],
+    ago: &["il y a"],
+    now: &["à l'instant", "maintenant"],
+    today: &["aujourd'hui"],
+    yesterday: &["hier"],
+};
+
+const DE: Words = Words {
+    months: [
+        "januar",
+        "februar",
+        "märz",
+        "april",
+        "mai",
+        "juni",
+        "juli",
+        "august",
+        "september",
+        "oktober",
+        "november",
+        "dezember",
+    ],
+    units: &[
+        ("sekunde", Unit::Second),
+        ("minute", Unit::Minute),
+        ("stunde", Unit::Hour),
+        ("tag", Unit::Day),
+        ("woche", Unit::Week),
+        ("monat", Unit::Month),
+        ("jahr", Unit::Year),
+    ],
+    one: &["ein", "einem", "einer"],
+    ago: &["vor"],
+    now: &["gerade eben", "jetzt"],
+    today: &["heute"],
+    yesterday: &["gestern"],
+};
+
+const ID: Words = Words {
+    months: [
+        "januari",
+        "februari",
+        "maret",
+        "april",
+        "mei",
+        "juni",
+        "juli",
+        "agustus",
+        "september",
+        "oktober",
+        "november",
+        "desember",
+    ],
+    units: &[
+        ("detik", Unit::Second),
+        ("menit", Unit::Minute),
+        ("jam", Unit::Hour),
+        ("hari", Unit::Day),
+        ("minggu", Unit::Week),
+        ("bulan", Unit::Month),
+        ("tahun", Unit::Year),
+    ],
+    one: &["satu", "se"],
+    ago: &["yang lalu", "lalu"],
+    now: &["baru saja", "sekarang"],
+    today: &["hari ini"],
+    yesterday: &["kemarin"],
+};
+
+const TR: Words = Words {
+    months: [
+        "ocak", "şubat", "mart", "nisan", "mayıs", "haziran", "temmuz", "ağustos", "eylül", "ekim",
+        "kasım", "aralık",
+    ],
+    units: &[
+        ("saniye", Unit::Second),
+        ("dakika", Unit::Minute),
+        ("saat", Unit::Hour),
+        ("gün", Unit::Day),
+        ("gun", Unit::Day),
+        ("hafta", Unit::Week),
+        ("ay", Unit::Month),
+        ("yıl", Unit::Year),
+        ("yil", Unit::Year),
+    ],
+    one: &["bir"],
+    ago: &["önce", "once"],
+    now: &["şimdi", "az önce"],
+    today: &["bugün"],
+    yesterday: &["dün"],
+};
+
+/// When a chapter was uploaded, from dates as sites show them: absolute ones
+/// such as `January 5, 2022`, `05/01/2022` or `2022-01-05 10:30`, relative
+/// ones such as `2 days ago` or `hace 3 horas`, and `Today` or `Yesterday`.
+///
+/// Returns `None` for anything else, leaving the fallback to the source.
+pub fn parse_upload_date(date: &str, locale: Locale) -> Option<NaiveDateTime> {
+    parse_upload_date_at(date, locale, Utc::now().naive_utc())
+}
+
+/// [`parse_upload_date`] with relative dates counted back from `now`.
+pub fn parse_upload_date_at(
+    date: &str,
+    locale: Locale,
+    now: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    let tokens = tokenize(date);
+    if tokens.is_empty() {
+        return None;
+    }
+    let text = format!(" {} ", tokens.join(" "));
+
+    let mut words = vec![locale.words()];
+    if locale != Locale::En {
+        words.push(&EN);
+    }
+
+    words
+        .iter()
+        .find_map(|words| parse_relative(&tokens, &text, words, now))
+        .or_else(|| parse_absolute(&tokens, locale, &words, now))
+}
+
+/// Lowercase words, numbers and `hh:mm[:ss]` times, in order. Digits and
+/// letters are split apart, so `2h` is `2 h` and `1st` is `1 st`.
+fn tokenize(date: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = vec![];
+    let mut current = String::new();
+    let mut previous: Option<char> = None;
+    for c in date.to_lowercase().chars() {
+        let joins = match previous {
+            _ if current.is_empty() => true,
+            Some(p) if p.is_alphabetic() => c.is_alphabetic() || c == '\'',
+            Some('\'') => c.is_alphabetic(),
+            Some(p) if p.is_ascii_digit() => c.is_ascii_digit() || c == ':',
+            Some(':') => c.is_ascii_digit(),
+            _ => false,
+        };
+        if !joins {
+            tokens.push(std::mem::take(&mut current));
+        }
+        if c.is_alphanumeric() || (c == ':' || c == '\'') && !current.is_empty() {
+            current.push(c);
+        }
+        previous = Some(c);
+    }
+    tokens.push(current);
+
+    tokens
+        .into_iter()
+        .map(|token| token.trim_end_matches([':', '\'']).to_string())
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+fn contains_phrase(text: &str, phrases: &[&str]) -> bool {
+    phrases
+        .iter()
+        .any(|phrase| text.contains(&format!(" {} ", phrase)))
+}
+
+fn matches_stem(token: &str, stem: &str) -> bool {
+    token == stem || (stem.chars().count() > 2 && token.starts_with(stem))
+}
+
+fn parse_relative(
+    tokens: &[String],
+    text: &str,
+    words: &Words,
+    now: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    let number = tokens.iter().find_map(|token| token.parse::<i64>().ok());
+    let unit = tokens.iter().find_map(|token| {
+        words
+            .units
+            .iter()
+            .find(|(stem, _)| matches_stem(token, stem))
+            .map(|(_, unit)| *unit)
+    });
+
+    if let Some(unit) = unit {
+        if contains_phrase(text, words.ago) {
+            let one = tokens
+                .iter()
+                .any(|token| words.one.contains(&token.as_str()));
+            let amount = number.or(if one { Some(1) } else { None })?;
+            return now.checked_sub_signed(unit.duration(amount));
+        }
+    }
+
+    if contains_phrase(text, words.now) {
+        return Some(now);
+    }
+
+    let days_back = if contains_phrase(text, words.today) {
+        0
+    } else if contains_phrase(text, words.yesterday) {
+        1
+    } else {
+        return None;
+    };
+    let date = now.date() - Duration::days(days_back);
+    let time = tokens
+        .iter()
+        .find_map(|token| parse_time(token, tokens))
+        .unwrap_or_else(|| now.time());
+    Some(date.and_time(time))
+}
+
+/// A `hh:mm[:ss]` token, moved to the afternoon if `pm` follows somewhere.
+fn parse_time(token: &str, tokens: &[String]) -> Option<NaiveTime> {
+    let mut parts = token.split(':');
+    let mut hour: u32 = parts.next()?.parse().ok()?;
+    let minute: u32 = parts.next()?.parse().ok()?;
+    let second: u32 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+
+    let pm = tokens.iter().any(|token| token == "pm");
+    let am = tokens.iter().any(|token| token == "am");
+    if pm && hour < 12 {
+        hour += 12;
+    } else if am && hour == 12 {
+        hour = 0;
+    }
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+fn parse_month(token: &str, words: &[&Words]) -> Option<u32> {
+    if token.chars().count() < 3 {
+        return None;
+    }
+    words.iter().find_map(|words| {
+        words
+            .months
+            .iter()
+            .position(|month| month.starts_with(token) || token.starts_with(month))
+            .map(|index| index as u32 + 1)
+    })
+}
+
+fn full_year(year: u32) -> i32 {
+    if year < 100 {
+        2000 + year as i32
+    } else {
+        year as i32
+    }
+}
+
+fn parse_absolute(
+    tokens: &[String],
+    locale: Locale,
+    words: &[&Words],
+    now: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    let mut numbers: Vec<&str> = vec![];
+    let mut month = None;
+    let mut time = None;
+    for token in tokens {
+        if token.contains(':') {
+            time = time.or_else(|| parse_time(token, tokens));
+        } else if token.chars().all(|c| c.is_ascii_digit()) {
+            numbers.push(token);
+        } else if month.is_none() {
+            month = parse_month(token, words);
+        }
+    }
+    let time = time.unwrap_or_else(|| NaiveTime::from_hms(0, 0, 0));
+
+    let date = match (month, numbers.as_slice()) {
+        // 2022-01-05
+        (None, [year, month, day]) if year.len() == 4 => {
+            NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+        }
+        // 05/01/2022, 01/05/22
+        (None, [a, b, year]) => {
+            let (a, b): (u32, u32) = (a.parse().ok()?, b.parse().ok()?);
+            let year = full_year(year.parse().ok()?);
+            let (month, day) = if (locale.month_first() && a <= 12) || b > 12 {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            NaiveDate::from_ymd_opt(year, month, day)
+        }
+        // January 5, 2022, 5 Jan 22, 2022 Jan 5
+        (Some(month), [a, b]) => {
+            let (day, year) = if a.len() == 4 || b.len() == 1 {
+                (b, a)
+            } else {
+                (a, b)
+            };
+            NaiveDate::from_ymd_opt(full_year(year.parse().ok()?), month, day.parse().ok()?)
+        }
+        // January 5, in the last year
+        (Some(month), [day]) if day.len() <= 2 => {
+            let day = day.parse().ok()?;
+            NaiveDate::from_ymd_opt(now.year(), month, day)
+                .filter(|date| *date <= now.date())
+                .or_else(|| NaiveDate::from_ymd_opt(now.year() - 1, month, day))
+        }
+        _ => None,
+    }?;
+
+    Some(date.and_time(time))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd(2022, 3, 15).and_hms(12, 0, 0)
+    }
+
+    #[test]
+    fn test_parse_upload_date() {
+        let cases = [
+            // absolute dates as madara, themesia and mangakakalot show them
+            ("January 5, 2022", Locale::En, Some("2022-01-05 00:00:00")),
+            ("05 Jan 2022", Locale::En, Some("2022-01-05 00:00:00")),
+            ("Jan 05,2022 10:30", Locale::En, Some("2022-01-05 10:30:00")),
+            (
+                "September 21st, 2021",
+                Locale::En,
+                Some("2021-09-21 00:00:00"),
+            ),
+            ("2022-01-05", Locale::En, Some("2022-01-05 00:00:00")),
+            (
+                "2022-01-05 18:04:01",
+                Locale::En,
+                Some("2022-01-05 18:04:01"),
+            ),
+            ("01/05/2022", Locale::En, Some("2022-01-05 00:00:00")),
+            ("25/01/2022", Locale::En, Some("2022-01-25 00:00:00")),
+            ("05/01/2022", Locale::Es, Some("2022-01-05 00:00:00")),
+            (
+                "Jan 5, 2022 3:15 pm",
+                Locale::En,
+                Some("2022-01-05 15:15:00"),
+            ),
+            ("Dec 24", Locale::En, Some("2021-12-24 00:00:00")),
+            ("Mar 10", Locale::En, Some("2022-03-10 00:00:00")),
+            // relative dates
+            ("just now", Locale::En, Some("2022-03-15 12:00:00")),
+            ("5 mins ago", Locale::En, Some("2022-03-15 11:55:00")),
+            ("an hour ago", Locale::En, Some("2022-03-15 11:00:00")),
+            ("2 hour ago", Locale::En, Some("2022-03-15 10:00:00")),
+            ("3h ago", Locale::En, Some("2022-03-15 09:00:00")),
+            ("2 days ago", Locale::En, Some("2022-03-13 12:00:00")),
+            ("1 week ago", Locale::En, Some("2022-03-08 12:00:00")),
+            ("a month ago", Locale::En, Some("2022-02-13 12:00:00")),
+            ("1 year ago", Locale::En, Some("2021-03-15 12:00:00")),
+            ("Today", Locale::En, Some("2022-03-15 12:00:00")),
+            ("Yesterday", Locale::En, Some("2022-03-14 12:00:00")),
+            ("Yesterday 08:30", Locale::En, Some("2022-03-14 08:30:00")),
+            // other languages
+            (
+                "5 de enero de 2022",
+                Locale::Es,
+                Some("2022-01-05 00:00:00"),
+            ),
+            ("hace 3 horas", Locale::Es, Some("2022-03-15 09:00:00")),
+            ("hace un día", Locale::Es, Some("2022-03-14 12:00:00")),
+            ("ayer", Locale::Es, Some("2022-03-14 12:00:00")),
+            ("há 2 dias", Locale::Pt, Some("2022-03-13 12:00:00")),
+            (
+                "12 de março de 2022",
+                Locale::Pt,
+                Some("2022-03-12 00:00:00"),
+            ),
+            ("il y a 2 semaines", Locale::Fr, Some("2022-03-01 12:00:00")),
+            ("3 février 2022", Locale::Fr, Some("2022-02-03 00:00:00")),
+            ("aujourd'hui", Locale::Fr, Some("2022-03-15 12:00:00")),
+            ("vor 4 Tagen", Locale::De, Some("2022-03-11 12:00:00")),
+            ("3. März 2022", Locale::De, Some("2022-03-03 00:00:00")),
+            ("2 hari yang lalu", Locale::Id, Some("2022-03-13 12:00:00")),
+            ("Februari 3, 2022", Locale::Id, Some("2022-02-03 00:00:00")),
+            ("2 gün önce", Locale::Tr, Some("2022-03-13 12:00:00")),
+            ("3 Şubat 2022", Locale::Tr, Some("2022-02-03 00:00:00")),
+            // english still works for other locales
+            ("2 days ago", Locale::Es, Some("2022-03-13 12:00:00")),
+            // not dates
+            ("", Locale::En, None),
+            ("New", Locale::En, None),
+            ("Chapter 5", Locale::En, None),
+            ("31/31/2022", Locale::En, None),
+        ];
+
+        for (input, locale, expected) in cases {
+            let parsed = parse_upload_date_at(input, locale, now())
+                .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string());
+            assert_eq!(parsed.as_deref(), expected, "{:?} in {:?}", input, locale);
+        }
+    }
+
+    #[test]
+    fn test_locale_from_lang() {
+        assert_eq!(Locale::from_lang("es"), Locale::Es);
+        assert_eq!(Locale::from_lang("pt-BR"), Locale::Pt);
+        assert_eq!(Locale::from_lang("ko"), Locale::En);
+    }
+}
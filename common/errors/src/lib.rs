@@ -0,0 +1,129 @@
+use std::{fmt, time::Duration};
+
+use scraper::Selector;
+
+/// Why a source failed, for the host to tell a broken source apart from one
+/// that only needs to be tried again later.
+///
+/// Sources return it wrapped in [`anyhow::Error`], usually as context on top
+/// of the underlying error, so use [`SourceError::find`] to get it back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceError {
+    /// The site answered with an error status.
+    Network(u16),
+    /// The site wants a browser to solve a Cloudflare challenge first.
+    CloudflareChallenge,
+    /// The site refuses more requests for now, optionally saying for how long.
+    RateLimited(Option<Duration>),
+    /// An element or value could not be read from the page.
+    ParseError {
+        selector: String,
+        field: Option<String>,
+    },
+    /// The requested manga or chapter does not exist (anymore).
+    NotFound,
+    /// The page no longer looks like the source expects.
+    SiteStructureChanged(String),
+}
+
+impl SourceError {
+    /// The failure of an invalid `selector`.
+    pub fn selector(selector: &str) -> Self {
+        Self::ParseError {
+            selector: selector.to_string(),
+            field: None,
+        }
+    }
+
+    /// `field` could not be read with `selector`.
+    pub fn field(selector: &str, field: &str) -> Self {
+        Self::ParseError {
+            selector: selector.to_string(),
+            field: Some(field.to_string()),
+        }
+    }
+
+    /// The source error anywhere in the chain of `error`.
+    pub fn find(error: &anyhow::Error) -> Option<&Self> {
+        error
+            .downcast_ref()
+            .or_else(|| error.chain().find_map(|cause| cause.downcast_ref()))
+    }
+
+    /// Whether the same request may succeed when tried again later.
+    pub fn is_temporary(&self) -> bool {
+        match self {
+            Self::Network(status) => *status == 408 || *status >= 500,
+            Self::CloudflareChallenge | Self::RateLimited(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the source needs fixing before it works again.
+    pub fn is_broken(&self) -> bool {
+        matches!(
+            self,
+            Self::ParseError { .. } | Self::SiteStructureChanged(_)
+        )
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(status) => write!(f, "site answered with status {}", status),
+            Self::CloudflareChallenge => write!(f, "site is behind a Cloudflare challenge"),
+            Self::RateLimited(Some(retry_after)) => write!(
+                f,
+                "rate limited by the site, retry in {}s",
+                retry_after.as_secs()
+            ),
+            Self::RateLimited(None) => write!(f, "rate limited by the site"),
+            Self::ParseError {
+                selector,
+                field: Some(field),
+            } => write!(f, "failed to parse {} with selector `{}`", field, selector),
+            Self::ParseError {
+                selector,
+                field: None,
+            } => write!(f, "failed to parse selector `{}`", selector),
+            Self::NotFound => write!(f, "not found"),
+            Self::SiteStructureChanged(what) => write!(f, "site structure changed: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+/// [`Selector::parse`] failing with a [`SourceError`].
+pub fn parse_selector(selector: &str) -> Result<Selector, SourceError> {
+    Selector::parse(selector).map_err(|_| SourceError::selector(selector))
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_find_in_context() {
+        let error = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::Other))
+            .context(SourceError::Network(503))
+            .context("failed to get latest manga")
+            .unwrap_err();
+
+        let source_error = SourceError::find(&error).unwrap();
+        assert_eq!(source_error, &SourceError::Network(503));
+        assert!(source_error.is_temporary());
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        assert!(parse_selector("div.page-item-detail").is_ok());
+
+        let error = parse_selector("div[").unwrap_err();
+        assert!(error.is_broken());
+        assert_eq!(error.to_string(), "failed to parse selector `div[`");
+    }
+}
@@ -0,0 +1,246 @@
+use dateparse::Locale;
+use tanoshi_lib::prelude::{Lang, SourceInfo};
+
+use crate::filter::DEFAULT_GENRES;
+
+/// What the chapters of a series hold, as `_wp_manga_chapter_type` says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChapterType {
+    /// Chapters of images.
+    Manga,
+    /// Chapters of a novel, written in the page.
+    Text,
+    Video,
+}
+
+impl ChapterType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Manga => "manga",
+            Self::Text => "text",
+            Self::Video => "video",
+        }
+    }
+}
+
+/// Where a series lists its chapters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChapterEndpoint {
+    /// `POST {series}ajax/chapters`, used by current versions of the theme.
+    Ajax,
+    /// The `#chapterlist` on the page of the series, used by older versions.
+    Old,
+}
+
+/// Where the popular, latest and search listings come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListEndpoint {
+    /// `madara_load_more` requests to `wp-admin/admin-ajax.php`.
+    Ajax,
+    /// Plain pages, with `{page}` and `{query}` replaced in the paths, such
+    /// as `/webtoons/{page}?orderby=latest`. Filters are not supported on
+    /// them: the site has no filter list and a search without a query finds
+    /// nothing.
+    Paged {
+        popular: &'static str,
+        latest: &'static str,
+        search: &'static str,
+    },
+}
+
+/// Selectors a site overrides because its copy of the theme differs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selectors {
+    /// A series in the popular and latest listings.
+    pub manga_item: &'static str,
+    /// A series in the search results. When it matches the `<a>` of the
+    /// series itself, that link is the path of the series.
+    pub search_item: &'static str,
+    /// The name of a chapter, by default the one of the endpoint.
+    pub chapter_name: Option<&'static str>,
+    /// The images of a chapter.
+    pub pages: &'static str,
+}
+
+impl Default for Selectors {
+    fn default() -> Self {
+        Self {
+            manga_item: "div.page-item-detail",
+            search_item: "div.c-tabs-item__content",
+            chapter_name: None,
+            pages: "div.page-break, li.blocks-gallery-item, reading-content, div.theimage, img",
+        }
+    }
+}
+
+/// Everything that sets one Madara site apart from another, starting from
+/// the defaults of the current theme.
+#[derive(Debug, Clone)]
+pub struct MadaraConfig {
+    pub source: SourceInfo,
+    pub list_endpoint: ListEndpoint,
+    pub chapter_endpoint: ChapterEndpoint,
+    pub selectors: Selectors,
+    /// `chrono` formats of the upload dates, tried before the words of
+    /// `locale`.
+    pub date_formats: Vec<&'static str>,
+    pub locale: Locale,
+    pub posts_per_page: u32,
+    /// The chapter type listings are filtered on, or `None` to list every
+    /// type.
+    pub chapter_type: Option<ChapterType>,
+    /// `(language, path prefix)` of each language of the site, such as
+    /// `("es", "/es")`. Empty for a site in the languages of `source`.
+    pub languages: Vec<(&'static str, &'static str)>,
+    /// Index in `languages` of the one listings are in.
+    pub selected_language: usize,
+    /// Genres of the genre filter.
+    pub genres: Vec<&'static str>,
+    /// Search through the `/?s=` page rather than `admin-ajax.php`, for sites
+    /// that turned the ajax search off.
+    pub search_page: bool,
+    /// The site sits behind Cloudflare: go through FlareSolverr when
+    /// `FLARESOLVERR_URL` is set and keep the clearance cookies.
+    pub flaresolverr: bool,
+}
+
+impl MadaraConfig {
+    pub fn new(source: SourceInfo) -> Self {
+        Self {
+            source,
+            list_endpoint: ListEndpoint::Ajax,
+            chapter_endpoint: ChapterEndpoint::Ajax,
+            selectors: Selectors::default(),
+            date_formats: vec![],
+            locale: Locale::default(),
+            posts_per_page: 20,
+            chapter_type: Some(ChapterType::Manga),
+            languages: vec![],
+            selected_language: 0,
+            genres: DEFAULT_GENRES.to_vec(),
+            search_page: false,
+            flaresolverr: false,
+        }
+    }
+
+    pub fn list_endpoint(mut self, list_endpoint: ListEndpoint) -> Self {
+        self.list_endpoint = list_endpoint;
+        self
+    }
+
+    pub fn chapter_endpoint(mut self, chapter_endpoint: ChapterEndpoint) -> Self {
+        self.chapter_endpoint = chapter_endpoint;
+        self
+    }
+
+    pub fn manga_item_selector(mut self, selector: &'static str) -> Self {
+        self.selectors.manga_item = selector;
+        self
+    }
+
+    pub fn search_item_selector(mut self, selector: &'static str) -> Self {
+        self.selectors.search_item = selector;
+        self
+    }
+
+    pub fn chapter_name_selector(mut self, selector: &'static str) -> Self {
+        self.selectors.chapter_name = Some(selector);
+        self
+    }
+
+    pub fn pages_selector(mut self, selector: &'static str) -> Self {
+        self.selectors.pages = selector;
+        self
+    }
+
+    pub fn date_format(mut self, format: &'static str) -> Self {
+        self.date_formats.push(format);
+        self
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn posts_per_page(mut self, posts_per_page: u32) -> Self {
+        self.posts_per_page = posts_per_page;
+        self
+    }
+
+    pub fn chapter_type(mut self, chapter_type: Option<ChapterType>) -> Self {
+        self.chapter_type = chapter_type;
+        self
+    }
+
+    /// Adds a language of the site, whose pages are under `prefix`. The
+    /// first one added is listed unless another is selected.
+    pub fn language(mut self, language: &'static str, prefix: &'static str) -> Self {
+        self.languages.push((language, prefix));
+        self
+    }
+
+    /// Lists the series of `language`, if the site has it.
+    pub fn select_language(&mut self, language: &str) -> bool {
+        match self.languages.iter().position(|(lang, _)| *lang == language) {
+            Some(index) => {
+                self.selected_language = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn genres(mut self, genres: &[&'static str]) -> Self {
+        self.genres = genres.to_vec();
+        self
+    }
+
+    pub fn search_page(mut self, search_page: bool) -> Self {
+        self.search_page = search_page;
+        self
+    }
+
+    pub fn flaresolverr(mut self, flaresolverr: bool) -> Self {
+        self.flaresolverr = flaresolverr;
+        self
+    }
+
+    pub fn id(&self) -> i64 {
+        self.source.id
+    }
+
+    /// The base URL, without a trailing slash.
+    pub fn url(&self) -> &str {
+        self.source.url.trim_end_matches('/')
+    }
+
+    /// The base URL of the selected language, where listings and searches
+    /// are. Series and chapters keep the prefix in their paths.
+    pub fn list_url(&self) -> String {
+        let prefix = self
+            .languages
+            .get(self.selected_language)
+            .map(|(_, prefix)| prefix.trim_end_matches('/'))
+            .unwrap_or_default();
+        format!("{}{}", self.url(), prefix)
+    }
+
+    /// `source`, with the languages of the site when it has several.
+    pub fn source_info(&self) -> SourceInfo {
+        let mut source = self.source.clone();
+        match self.languages.as_slice() {
+            [] => {}
+            [(language, _)] => source.languages = Lang::Single(language.to_string()),
+            languages => {
+                source.languages = Lang::Multi(
+                    languages
+                        .iter()
+                        .map(|(language, _)| language.to_string())
+                        .collect(),
+                )
+            }
+        }
+        source
+    }
+}
@@ -0,0 +1,378 @@
+use std::fmt;
+
+use anyhow::Result;
+use errors::parse_selector;
+use scraper::{ElementRef, Html};
+use tanoshi_lib::prelude::MangaInfo;
+
+use crate::{get_data_src, MadaraConfig};
+
+/// Status of a series, whatever words the site uses for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ongoing,
+    Completed,
+    Hiatus,
+    Cancelled,
+}
+
+/// Words sites write the status in, in lower case and without spaces.
+const STATUS_WORDS: &[(&str, Status)] = &[
+    ("ongoing", Status::Ongoing),
+    ("releasing", Status::Ongoing),
+    ("publishing", Status::Ongoing),
+    ("emandamento", Status::Ongoing),
+    ("andamento", Status::Ongoing),
+    ("encurso", Status::Ongoing),
+    ("emiss", Status::Ongoing),
+    ("enemisi", Status::Ongoing),
+    ("encours", Status::Ongoing),
+    ("berjalan", Status::Ongoing),
+    ("devamediyor", Status::Ongoing),
+    ("laufend", Status::Ongoing),
+    ("completed", Status::Completed),
+    ("complete", Status::Completed),
+    ("end", Status::Completed),
+    ("finished", Status::Completed),
+    ("completo", Status::Completed),
+    ("concluído", Status::Completed),
+    ("concluido", Status::Completed),
+    ("finalizado", Status::Completed),
+    ("terminé", Status::Completed),
+    ("termine", Status::Completed),
+    ("tamat", Status::Completed),
+    ("tamamlandı", Status::Completed),
+    ("abgeschlossen", Status::Completed),
+    ("onhold", Status::Hiatus),
+    ("hiatus", Status::Hiatus),
+    ("pausado", Status::Hiatus),
+    ("enpausa", Status::Hiatus),
+    ("enpause", Status::Hiatus),
+    ("canceled", Status::Cancelled),
+    ("cancelled", Status::Cancelled),
+    ("dropped", Status::Cancelled),
+    ("cancelado", Status::Cancelled),
+    ("abandonado", Status::Cancelled),
+    ("annulé", Status::Cancelled),
+    ("abandonné", Status::Cancelled),
+];
+
+impl Status {
+    /// The status `text` is written in, such as `OnGoing`, `Em andamento`
+    /// or `Terminé`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text: String = text
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect();
+        STATUS_WORDS
+            .iter()
+            .find(|(word, _)| text == *word)
+            .or_else(|| {
+                STATUS_WORDS
+                    .iter()
+                    .find(|(word, _)| word.len() > 3 && text.starts_with(word))
+            })
+            .map(|(_, status)| *status)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ongoing => "Ongoing",
+            Self::Completed => "Completed",
+            Self::Hiatus => "Hiatus",
+            Self::Cancelled => "Cancelled",
+        })
+    }
+}
+
+/// Everything the page of a series says about it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MangaDetail {
+    pub title: String,
+    pub alternative_titles: Vec<String>,
+    pub authors: Vec<String>,
+    pub artists: Vec<String>,
+    pub genres: Vec<String>,
+    pub status: Option<Status>,
+    /// Manga, Manhwa, Manhua and the like.
+    pub kind: Option<String>,
+    pub release_year: Option<i32>,
+    /// The average rating, out of 5.
+    pub rating: Option<f64>,
+    pub description: Option<String>,
+    pub cover_url: String,
+}
+
+impl MangaDetail {
+    /// The detail as the host keeps it: artists follow the authors and the
+    /// status is one of the common words.
+    pub fn into_manga_info(self, config: &MadaraConfig, path: &str) -> MangaInfo {
+        let mut author = self.authors;
+        for artist in self.artists {
+            if !author.contains(&artist) {
+                author.push(artist);
+            }
+        }
+
+        MangaInfo {
+            source_id: config.id(),
+            title: self.title,
+            author,
+            genre: self.genres,
+            status: self.status.map(|status| status.to_string()),
+            description: self.description,
+            path: path.to_string().replace(config.url(), ""),
+            cover_url: self.cover_url,
+        }
+    }
+}
+
+/// Which row of `post-content_item` a heading names, in the languages the
+/// theme is translated to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Alternative,
+    Author,
+    Artist,
+    Genre,
+    Kind,
+    Release,
+    Status,
+    Rating,
+}
+
+const ROW_HEADINGS: &[(&str, Row)] = &[
+    ("alternative", Row::Alternative),
+    ("alternativo", Row::Alternative),
+    ("other name", Row::Alternative),
+    ("nombre", Row::Alternative),
+    ("nome", Row::Alternative),
+    ("autre", Row::Alternative),
+    ("author", Row::Author),
+    ("autor", Row::Author),
+    ("auteur", Row::Author),
+    ("penulis", Row::Author),
+    ("yazar", Row::Author),
+    ("artist", Row::Artist),
+    ("artista", Row::Artist),
+    ("artiste", Row::Artist),
+    ("ilustrador", Row::Artist),
+    ("seniman", Row::Artist),
+    ("çizer", Row::Artist),
+    ("genre", Row::Genre),
+    ("género", Row::Genre),
+    ("gênero", Row::Genre),
+    ("genero", Row::Genre),
+    ("tür", Row::Genre),
+    ("type", Row::Kind),
+    ("tipo", Row::Kind),
+    ("tipe", Row::Kind),
+    ("release", Row::Release),
+    ("lanzamiento", Row::Release),
+    ("lançamento", Row::Release),
+    ("année", Row::Release),
+    ("rilis", Row::Release),
+    ("status", Row::Status),
+    ("estado", Row::Status),
+    ("statut", Row::Status),
+    ("durum", Row::Status),
+    ("rating", Row::Rating),
+    ("calificación", Row::Rating),
+    ("avaliação", Row::Rating),
+    ("note", Row::Rating),
+];
+
+fn row(heading: &str) -> Option<Row> {
+    let heading = heading.to_lowercase();
+    ROW_HEADINGS
+        .iter()
+        .find(|(word, _)| heading.contains(word))
+        .map(|(_, row)| *row)
+}
+
+fn text(el: &ElementRef) -> String {
+    el.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn links(el: &ElementRef) -> Result<Vec<String>> {
+    let selector = parse_selector("a")?;
+    Ok(el
+        .select(&selector)
+        .map(|a| text(&a))
+        .filter(|name| !name.is_empty())
+        .collect())
+}
+
+/// Reads the page of a series.
+pub fn parse_manga_detail(body: &str) -> Result<MangaDetail> {
+    let doc = Html::parse_document(body);
+
+    let selector_name = parse_selector(r#"div.post-title h3, div.post-title h1, div.series-title h1"#)?;
+
+    let selector_img = parse_selector(".summary_image img, .series-img img")?;
+
+    let selector_row = parse_selector(".post-content_item")?;
+
+    let selector_heading = parse_selector(".summary-heading")?;
+
+    let selector_content = parse_selector(".summary-content")?;
+
+    let selector_author = parse_selector(".author-content a")?;
+
+    let selector_artist = parse_selector(".artist-content a")?;
+
+    let selector_genre = parse_selector(r#".genres-content a"#)?;
+
+    let selector_score = parse_selector(r#".post-total-rating .score, [property="ratingValue"], #averagerate"#)?;
+
+    let selector_desc = parse_selector("div.description-summary div.summary__content, div.summary_content div.post-content_item > h5 + div, div.summary_content div.manga-excerpt, div.summary-text p")?;
+
+    let mut detail = MangaDetail {
+        title: doc
+            .select(&selector_name)
+            .next()
+            .and_then(|item| item.last_child())
+            .and_then(|t| t.value().as_text())
+            .map(|t| t.trim().to_string())
+            .unwrap_or_default(),
+        authors: doc.select(&selector_author).map(|el| text(&el)).collect(),
+        artists: doc.select(&selector_artist).map(|el| text(&el)).collect(),
+        genres: doc.select(&selector_genre).map(|el| text(&el)).collect(),
+        rating: doc
+            .select(&selector_score)
+            .find_map(|el| text(&el).parse().ok()),
+        description: Some(
+            doc.select(&selector_desc)
+                .flat_map(|el| el.text())
+                .collect::<Vec<&str>>()
+                .join("")
+                .trim()
+                .to_string(),
+        ),
+        cover_url: doc
+            .select(&selector_img)
+            .find_map(|el| get_data_src(&el))
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    for el in doc.select(&selector_row) {
+        let (heading, content) = match (
+            el.select(&selector_heading).next(),
+            el.select(&selector_content).next(),
+        ) {
+            (Some(heading), Some(content)) => (text(&heading), content),
+            _ => continue,
+        };
+
+        match row(&heading) {
+            Some(Row::Alternative) => {
+                detail.alternative_titles = text(&content)
+                    .split(|c| matches!(c, ',' | ';' | '/' | '|'))
+                    .map(|title| title.trim().to_string())
+                    .filter(|title| !title.is_empty() && !title.eq_ignore_ascii_case("updating"))
+                    .collect();
+            }
+            Some(Row::Author) if detail.authors.is_empty() => detail.authors = links(&content)?,
+            Some(Row::Artist) if detail.artists.is_empty() => detail.artists = links(&content)?,
+            Some(Row::Genre) if detail.genres.is_empty() => detail.genres = links(&content)?,
+            Some(Row::Kind) => {
+                detail.kind = Some(text(&content)).filter(|kind| !kind.is_empty());
+            }
+            Some(Row::Release) => {
+                detail.release_year = text(&content)
+                    .split(|c: char| !c.is_ascii_digit())
+                    .find(|year| year.len() == 4)
+                    .and_then(|year| year.parse().ok());
+            }
+            Some(Row::Status) => detail.status = Status::parse(&text(&content)),
+            Some(Row::Rating) if detail.rating.is_none() => {
+                detail.rating = text(&content)
+                    .split_whitespace()
+                    .find_map(|word| word.parse().ok());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(detail)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PAGE: &str = r##"
+        <div class="post-title"><h1><span class="manga-title-badges hot">HOT</span> Solo Leveling </h1></div>
+        <div class="summary_image"><img data-src="https://madara.local/cover.jpg"></div>
+        <div class="post-content">
+            <div class="post-rating"><div class="post-total-rating"><span class="score">4.7</span></div></div>
+            <div class="post-content_item">
+                <div class="summary-heading"><h5>Alternative</h5></div>
+                <div class="summary-content"> 나 혼자만 레벨업 ; Only I Level Up, I Level Up Alone </div>
+            </div>
+            <div class="post-content_item">
+                <div class="summary-heading"><h5>Author(s)</h5></div>
+                <div class="summary-content"><div class="author-content"><a href="#">Chugong</a></div></div>
+            </div>
+            <div class="post-content_item">
+                <div class="summary-heading"><h5>Artist(s)</h5></div>
+                <div class="summary-content"><div class="artist-content"><a href="#">Jang Sung-rak</a> <a href="#">Chugong</a></div></div>
+            </div>
+            <div class="post-content_item">
+                <div class="summary-heading"><h5>Genre(s)</h5></div>
+                <div class="summary-content"><div class="genres-content"><a href="#">Action</a>, <a href="#">Fantasy</a></div></div>
+            </div>
+            <div class="post-content_item">
+                <div class="summary-heading"><h5>Type</h5></div>
+                <div class="summary-content"> Manhwa </div>
+            </div>
+        </div>
+        <div class="post-status">
+            <div class="post-content_item">
+                <div class="summary-heading"><h5>Release</h5></div>
+                <div class="summary-content"><a href="#">2018</a></div>
+            </div>
+            <div class="post-content_item">
+                <div class="summary-heading"><h5>Status</h5></div>
+                <div class="summary-content"> OnGoing </div>
+            </div>
+        </div>
+        <div class="description-summary"><div class="summary__content"><p>Ten years ago.</p></div></div>
+    "##;
+
+    #[test]
+    fn test_parse_manga_detail() {
+        let detail = parse_manga_detail(PAGE).unwrap();
+
+        assert_eq!(detail.title, "Solo Leveling");
+        assert_eq!(
+            detail.alternative_titles,
+            vec!["나 혼자만 레벨업", "Only I Level Up", "I Level Up Alone"]
+        );
+        assert_eq!(detail.authors, vec!["Chugong"]);
+        assert_eq!(detail.artists, vec!["Jang Sung-rak", "Chugong"]);
+        assert_eq!(detail.genres, vec!["Action", "Fantasy"]);
+        assert_eq!(detail.kind.as_deref(), Some("Manhwa"));
+        assert_eq!(detail.release_year, Some(2018));
+        assert_eq!(detail.rating, Some(4.7));
+        assert_eq!(detail.status, Some(Status::Ongoing));
+        assert_eq!(detail.description.as_deref(), Some("Ten years ago."));
+        assert_eq!(detail.cover_url, "https://madara.local/cover.jpg");
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(Status::parse("OnGoing"), Some(Status::Ongoing));
+        assert_eq!(Status::parse("Em andamento"), Some(Status::Ongoing));
+        assert_eq!(Status::parse("End"), Some(Status::Completed));
+        assert_eq!(Status::parse("Terminé"), Some(Status::Completed));
+        assert_eq!(Status::parse("On Hold"), Some(Status::Hiatus));
+        assert_eq!(Status::parse("Dropped"), Some(Status::Cancelled));
+        assert_eq!(Status::parse("Updating"), None);
+    }
+}
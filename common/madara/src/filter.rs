@@ -0,0 +1,411 @@
+use lazy_static::lazy_static;
+use tanoshi_lib::prelude::{Input, InputType, TriState};
+use url::form_urlencoded::byte_serialize;
+
+use crate::{ListEndpoint, MadaraConfig};
+
+const GENRE_FILTER: &str = "Genres";
+
+/// Genres most Madara sites have. The slug of a genre is its name in lower
+/// case with dashes for spaces.
+pub const DEFAULT_GENRES: &[&str] = &[
+    "Action",
+    "Adult",
+    "Adventure",
+    "Comedy",
+    "Drama",
+    "Ecchi",
+    "Fantasy",
+    "Gender Bender",
+    "Harem",
+    "Historical",
+    "Horror",
+    "Isekai",
+    "Josei",
+    "Martial Arts",
+    "Mature",
+    "Mecha",
+    "Mystery",
+    "Psychological",
+    "Reincarnation",
+    "Romance",
+    "School Life",
+    "Sci-fi",
+    "Seinen",
+    "Shoujo",
+    "Shounen",
+    "Slice of Life",
+    "Smut",
+    "Sports",
+    "Supernatural",
+    "Tragedy",
+];
+
+/// `(name, value of _wp_manga_status)` of the statuses.
+const STATUSES: &[(&str, &str)] = &[
+    ("Ongoing", "on-going"),
+    ("Completed", "end"),
+    ("Canceled", "canceled"),
+    ("On Hold", "on-hold"),
+    ("Upcoming", "upcoming"),
+];
+
+/// What the ajax search orders the series by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Relevance,
+    Meta(&'static str),
+    Title,
+    Date,
+}
+
+/// `(name, m_orderby of the search page, ajax order)` of the sorts.
+const SORTS: &[(&str, &str, SortBy)] = &[
+    ("Relevance", "", SortBy::Relevance),
+    ("Latest Update", "latest", SortBy::Meta("_latest_update")),
+    ("Alphabetical", "alphabet", SortBy::Title),
+    ("Rating", "rating", SortBy::Meta("_manga_avarage_reviews")),
+    ("Trending", "trending", SortBy::Meta("_wp_manga_week_views_value")),
+    ("Most Views", "views", SortBy::Meta("_wp_manga_views")),
+    ("New", "new-manga", SortBy::Date),
+];
+
+lazy_static! {
+    pub static ref STATUS_FILTER: Input = Input::Group {
+        name: "Status".to_string(),
+        state: STATUSES
+            .iter()
+            .map(|(name, _)| Input::Checkbox {
+                name: name.to_string(),
+                state: None
+            })
+            .collect()
+    };
+    pub static ref ADULT_FILTER: Input = Input::Select {
+        name: "Adult Content".to_string(),
+        values: vec![
+            InputType::String("All".to_string()),
+            InputType::String("None".to_string()),
+            InputType::String("Only".to_string())
+        ],
+        state: None
+    };
+    pub static ref SORT_BY_FILTER: Input = Input::Sort {
+        name: "Sort By".to_string(),
+        values: SORTS
+            .iter()
+            .map(|(name, _, _)| InputType::String(name.to_string()))
+            .collect(),
+        selection: None
+    };
+}
+
+fn genre_filter(config: &MadaraConfig) -> Input {
+    Input::Group {
+        name: GENRE_FILTER.to_string(),
+        state: config
+            .genres
+            .iter()
+            .map(|name| Input::State {
+                name: name.to_string(),
+                selected: None,
+            })
+            .collect(),
+    }
+}
+
+/// The filters of a site, or none when its listings are not Madara's own.
+pub fn get_filter_list(config: &MadaraConfig) -> Vec<Input> {
+    if let ListEndpoint::Paged { .. } = config.list_endpoint {
+        return vec![];
+    }
+
+    vec![
+        genre_filter(config),
+        STATUS_FILTER.clone(),
+        ADULT_FILTER.clone(),
+        SORT_BY_FILTER.clone(),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adult {
+    All,
+    None,
+    Only,
+}
+
+/// A search as the filters describe it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search {
+    pub query: String,
+    /// Slugs of the genres a series must all have.
+    pub include_genres: Vec<String>,
+    /// Slugs of the genres a series must not have.
+    pub exclude_genres: Vec<String>,
+    /// Values of `_wp_manga_status`, any of which matches.
+    pub status: Vec<&'static str>,
+    pub adult: Adult,
+    /// Index in `SORTS` and whether it is ascending.
+    pub sort: (usize, bool),
+}
+
+impl Search {
+    pub fn new(query: Option<&str>, filters: &[Input]) -> Self {
+        let mut search = Self {
+            query: query.unwrap_or_default().to_string(),
+            include_genres: vec![],
+            exclude_genres: vec![],
+            status: vec![],
+            adult: Adult::All,
+            sort: (0, false),
+        };
+
+        for filter in filters {
+            match filter {
+                Input::Group { name, state } if name == GENRE_FILTER => {
+                    for genre in state {
+                        if let Input::State {
+                            name,
+                            selected: Some(selected),
+                        } = genre
+                        {
+                            match selected {
+                                TriState::Included => search.include_genres.push(slug(name)),
+                                TriState::Excluded => search.exclude_genres.push(slug(name)),
+                                TriState::Ignored => {}
+                            }
+                        }
+                    }
+                }
+                Input::Group { state, .. } if STATUS_FILTER.eq(filter) => {
+                    search.status = state
+                        .iter()
+                        .filter_map(|status| match status {
+                            Input::Checkbox {
+                                name,
+                                state: Some(true),
+                            } => STATUSES
+                                .iter()
+                                .find(|(status, _)| status == name)
+                                .map(|(_, value)| *value),
+                            _ => None,
+                        })
+                        .collect();
+                }
+                Input::Select {
+                    state: Some(state), ..
+                } if ADULT_FILTER.eq(filter) => {
+                    search.adult = match state {
+                        1 => Adult::None,
+                        2 => Adult::Only,
+                        _ => Adult::All,
+                    };
+                }
+                Input::Sort {
+                    selection: Some((index, asc)),
+                    ..
+                } if SORT_BY_FILTER.eq(filter) => {
+                    let index = *index as usize;
+                    if index < SORTS.len() {
+                        search.sort = (index, *asc);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        search
+    }
+
+    /// The `vars` of a `madara_load_more` search that apply the filters.
+    pub fn form_vars(&self, meta_query_start: usize) -> Vec<(String, String)> {
+        let mut vars = vec![];
+
+        let (_, _, sort_by) = SORTS[self.sort.0];
+        let order = if self.sort.1 { "asc" } else { "desc" };
+        let orderby = match sort_by {
+            SortBy::Relevance => None,
+            SortBy::Meta(meta_key) => {
+                vars.push(("vars[meta_key]".to_string(), meta_key.to_string()));
+                Some("meta_value_num")
+            }
+            SortBy::Title => Some("post_title"),
+            SortBy::Date => Some("date"),
+        };
+        if let Some(orderby) = orderby {
+            vars.push(("vars[orderby]".to_string(), orderby.to_string()));
+            vars.push(("vars[order]".to_string(), order.to_string()));
+        }
+
+        let mut taxonomies = vec![];
+        if !self.include_genres.is_empty() {
+            taxonomies.push((&self.include_genres, "AND"));
+        }
+        if !self.exclude_genres.is_empty() {
+            taxonomies.push((&self.exclude_genres, "NOT IN"));
+        }
+        if !taxonomies.is_empty() {
+            vars.push(("vars[tax_query][relation]".to_string(), "AND".to_string()));
+        }
+        for (i, (genres, operator)) in taxonomies.into_iter().enumerate() {
+            let clause = format!("vars[tax_query][{}]", i);
+            vars.push((format!("{}[taxonomy]", clause), "wp-manga-genre".to_string()));
+            vars.push((format!("{}[field]", clause), "slug".to_string()));
+            for genre in genres {
+                vars.push((format!("{}[terms][]", clause), genre.clone()));
+            }
+            vars.push((format!("{}[operator]", clause), operator.to_string()));
+        }
+
+        let mut meta_query = meta_query_start;
+        if !self.status.is_empty() {
+            let clause = format!("vars[meta_query][{}]", meta_query);
+            vars.push((format!("{}[key]", clause), "_wp_manga_status".to_string()));
+            for status in &self.status {
+                vars.push((format!("{}[value][]", clause), status.to_string()));
+            }
+            vars.push((format!("{}[compare]", clause), "IN".to_string()));
+            meta_query += 1;
+        }
+        match self.adult {
+            Adult::All => {}
+            Adult::Only => {
+                let clause = format!("vars[meta_query][{}]", meta_query);
+                vars.push((format!("{}[key]", clause), "manga_adult_content".to_string()));
+                vars.push((format!("{}[value]", clause), "yes".to_string()));
+                vars.push((format!("{}[compare]", clause), "LIKE".to_string()));
+            }
+            Adult::None => {
+                // series that never had the flag have no such meta at all
+                let clause = format!("vars[meta_query][{}]", meta_query);
+                vars.push((format!("{}[relation]", clause), "OR".to_string()));
+                vars.push((format!("{}[0][key]", clause), "manga_adult_content".to_string()));
+                vars.push((format!("{}[0][compare]", clause), "NOT EXISTS".to_string()));
+                vars.push((format!("{}[1][key]", clause), "manga_adult_content".to_string()));
+                vars.push((format!("{}[1][value]", clause), "yes".to_string()));
+                vars.push((format!("{}[1][compare]", clause), "NOT LIKE".to_string()));
+            }
+        }
+
+        vars
+    }
+
+    /// The search page of the theme with the filters in its query string.
+    /// It has no way to exclude a genre, so excluded genres are left out.
+    pub fn page_url(&self, url: &str, page: i64) -> String {
+        let mut query = vec![
+            ("s", self.query.clone()),
+            ("post_type", "wp-manga".to_string()),
+        ];
+        for genre in &self.include_genres {
+            query.push(("genre[]", genre.clone()));
+        }
+        if !self.include_genres.is_empty() {
+            // the series must have every genre, not any of them
+            query.push(("op", "1".to_string()));
+        }
+        for status in &self.status {
+            query.push(("status[]", status.to_string()));
+        }
+        match self.adult {
+            Adult::All => {}
+            Adult::None => query.push(("adult", "0".to_string())),
+            Adult::Only => query.push(("adult", "1".to_string())),
+        }
+        let (_, orderby, _) = SORTS[self.sort.0];
+        if !orderby.is_empty() {
+            query.push(("m_orderby", orderby.to_string()));
+        }
+
+        let query = query
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, byte_serialize(value.as_bytes()).collect::<String>()))
+            .collect::<Vec<_>>()
+            .join("&");
+        if page > 1 {
+            format!("{}/page/{}/?{}", url, page, query)
+        } else {
+            format!("{}/?{}", url, query)
+        }
+    }
+}
+
+fn slug(genre: &str) -> String {
+    genre.to_lowercase().split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tanoshi_lib::prelude::{Lang, SourceInfo};
+
+    fn filters() -> Vec<Input> {
+        let config = MadaraConfig::new(SourceInfo {
+            id: 1,
+            name: "Madara".to_string(),
+            url: "https://madara.local".to_string(),
+            version: "0.0.0",
+            icon: "",
+            languages: Lang::Single("en".to_string()),
+            nsfw: false,
+        });
+        let mut filters = get_filter_list(&config);
+        for filter in filters.iter_mut() {
+            match filter {
+                Input::Group { name, state } if name == GENRE_FILTER => {
+                    for genre in state.iter_mut() {
+                        if let Input::State { name, selected } = genre {
+                            *selected = match name.as_str() {
+                                "Martial Arts" => Some(TriState::Included),
+                                "Smut" => Some(TriState::Excluded),
+                                _ => None,
+                            };
+                        }
+                    }
+                }
+                Input::Group { state, .. } => {
+                    state[0] = Input::Checkbox {
+                        name: "Ongoing".to_string(),
+                        state: Some(true),
+                    };
+                }
+                Input::Select { state, .. } => *state = Some(1),
+                Input::Sort { selection, .. } => *selection = Some((5, false)),
+                _ => {}
+            }
+        }
+        filters
+    }
+
+    #[test]
+    fn test_search_from_filters() {
+        let search = Search::new(None, &filters());
+
+        assert_eq!(search.include_genres, vec!["martial-arts"]);
+        assert_eq!(search.exclude_genres, vec!["smut"]);
+        assert_eq!(search.status, vec!["on-going"]);
+        assert_eq!(search.adult, Adult::None);
+
+        let vars = search.form_vars(1);
+        let var = |key: &str| {
+            vars.iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(var("vars[meta_key]"), vec!["_wp_manga_views"]);
+        assert_eq!(var("vars[tax_query][0][terms][]"), vec!["martial-arts"]);
+        assert_eq!(var("vars[tax_query][1][operator]"), vec!["NOT IN"]);
+        assert_eq!(var("vars[meta_query][1][value][]"), vec!["on-going"]);
+        assert_eq!(
+            var("vars[meta_query][2][0][compare]"),
+            vec!["NOT EXISTS"]
+        );
+
+        assert_eq!(
+            search.page_url("https://madara.local", 2),
+            "https://madara.local/page/2/?s=&post_type=wp-manga&genre[]=martial-arts&op=1&status[]=on-going&adult=0&m_orderby=views"
+        );
+    }
+}
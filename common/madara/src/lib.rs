@@ -1,8 +1,26 @@
-use anyhow::{anyhow, Result};
-use chrono::NaiveDateTime;
+mod config;
+mod detail;
+mod filter;
+mod pagination;
+mod protector;
+mod source;
+
+pub use config::{ChapterEndpoint, ChapterType, ListEndpoint, MadaraConfig, Selectors};
+pub use detail::{parse_manga_detail, MangaDetail, Status};
+pub use filter::{get_filter_list, Adult, Search, DEFAULT_GENRES};
+pub use pagination::Pagination;
+pub use protector::protected_pages;
+pub use source::MadaraSource;
+
+use anyhow::{bail, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use errors::{parse_selector, SourceError};
+use chapterparse::parse_chapter_title;
+use dateparse::parse_upload_date;
 use scraper::{ElementRef, Html, Selector};
-use tanoshi_lib::prelude::{ChapterInfo, MangaInfo};
+use tanoshi_lib::prelude::{ChapterInfo, Input, MangaInfo};
 use networking::Agent;
+use url::form_urlencoded::byte_serialize;
 
 fn get_data_src(el: &ElementRef) -> Option<String> {
     el.value()
@@ -12,385 +30,412 @@ fn get_data_src(el: &ElementRef) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+/// Reads the series matched by `selector`. When it matches the `<a>` of a
+/// series, that link is the path of the series.
 pub fn parse_manga_list(
-    url: &str,
-    source_id: i64,
+    config: &MadaraConfig,
     body: &str,
     selector: &Selector,
-    is_selector_url: bool,
 ) -> Result<Vec<MangaInfo>> {
     let mut manga = vec![];
 
     let doc = Html::parse_document(body);
 
     for el in doc.select(selector) {
-        let selector_name = Selector::parse(if is_selector_url {
+        let is_link = el.value().name() == "a";
+
+        let name = if is_link {
             "div.item-summary > a > h3, div.data > h3 > a, div.post-title > h3"
         } else {
             "div.item-summary > a > h3, div.data > h3 > a, div.post-title > h3 > a"
-        })
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+        };
+        let selector_name = parse_selector(name)?;
 
-        let selector_url = Selector::parse("div.data a, div.post-title a, div.item-thumb a")
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+        let url = "div.data a, div.post-title a, div.item-thumb a";
+        let selector_url = parse_selector(url)?;
 
-        let selector_img =
-            Selector::parse("img").map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+        let selector_img = parse_selector("img")?;
 
-        manga.push(MangaInfo {
-            source_id,
-            title: el
-                .select(&selector_name)
+        let title = el
+            .select(&selector_name)
+            .next()
+            .and_then(|item| item.last_child())
+            .and_then(|t| t.value().as_text())
+            .map(|title| title.trim().to_string())
+            .ok_or_else(|| SourceError::field(name, "title"))?;
+
+        let path = if is_link {
+            el.value().attr("href")
+        } else {
+            el.select(&selector_url)
                 .next()
-                .and_then(|item| item.last_child())
-                .and_then(|t| t.value().as_text())
-                .unwrap()
-                .trim()
-                .to_string(),
+                .and_then(|el| el.value().attr("href"))
+        }
+        .ok_or_else(|| SourceError::field(if is_link { "a" } else { url }, "path"))?
+        .replace(config.url(), "");
+
+        manga.push(MangaInfo {
+            source_id: config.id(),
+            title,
             author: vec![],
             genre: vec![],
             status: None,
             description: None,
-            path: if is_selector_url {
-                el.value().attr("href").unwrap().replace(url, "")
-            } else {
-                el.select(&selector_url)
-                    .next()
-                    .unwrap()
-                    .value()
-                    .attr("href")
-                    .unwrap_or_default()
-                    .replace(url, "")
-            },
-            cover_url: get_data_src(&el.select(&selector_img).next().unwrap()).unwrap_or_default(),
+            path,
+            cover_url: el
+                .select(&selector_img)
+                .next()
+                .and_then(|el| get_data_src(&el))
+                .unwrap_or_default(),
         })
     }
 
     Ok(manga)
 }
 
-pub fn get_latest_manga(url: &str, source_id: i64, page: i64, client: &Agent) -> Result<Vec<MangaInfo>> {
-    let form: &[(&str, &str)] = &[
-        ("action", "madara_load_more"),
-        ("page", &(page - 1).to_string()),
-        ("template", "madara-core/content/content-archive"),
-        ("vars[orderby]", "meta_value_num"),
-        ("vars[paged]", "1"),
-        ("vars[posts_per_page]", "20"),
-        ("vars[post_type]", "wp-manga"),
-        ("vars[post_status]", "publish"),
-        ("vars[meta_key]", "_latest_update"),
-        ("vars[order]", "desc"),
-        ("vars[sidebar]", "right"),
-        ("vars[manga_archives_item_layout]", "big_thumbnail"),
-        ("vars[meta_query][0][key]", "_wp_manga_chapter_type"),
-        ("vars[meta_query][0][value]", "manga"),
-    ];
-    
-    let body = client.post(&format!("{}/wp-admin/admin-ajax.php", url))
-        .set("Referer", url)
+/// Fills `{page}` and the encoded `{query}` in a path of
+/// [`ListEndpoint::Paged`].
+fn paged_url(config: &MadaraConfig, path: &str, page: i64, query: &str) -> String {
+    format!(
+        "{}{}",
+        config.list_url(),
+        path.replace("{page}", &page.to_string())
+            .replace("{query}", &byte_serialize(query.as_bytes()).collect::<String>())
+    )
+}
+
+/// Reads page `page` of `listing`, or nothing once the site is past its end.
+/// `fragment` is whether the site answers with a piece of a page, as
+/// `madara_load_more` does.
+fn read_page(
+    config: &MadaraConfig,
+    pagination: &Pagination,
+    listing: &str,
+    page: i64,
+    body: &str,
+    selector: &str,
+    fragment: bool,
+) -> Result<Vec<MangaInfo>> {
+    if pagination::is_end(body, page, fragment) {
+        return Ok(vec![]);
+    }
+
+    let selector = parse_selector(selector)?;
+
+    let manga = parse_manga_list(config, body, &selector)?;
+
+    Ok(pagination.unseen(listing, page, manga))
+}
+
+fn get_paged_manga(
+    config: &MadaraConfig,
+    path: &str,
+    page: i64,
+    query: &str,
+    selector: &str,
+    pagination: &Pagination,
+    client: &Agent,
+) -> Result<Vec<MangaInfo>> {
+    let body = match client.get(&paged_url(config, path, page, query)).call() {
+        Ok(response) => response.into_string()?,
+        Err(e) if page > 1 && pagination::is_missing_page(&e) => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let listing = paged_url(config, path, 0, query);
+
+    read_page(config, pagination, &listing, page, &body, selector, false)
+}
+
+/// Posts `form` to `admin-ajax.php`, where the theme loads more series.
+fn load_more(config: &MadaraConfig, form: &[(&str, &str)], client: &Agent) -> Result<String> {
+    Ok(client.post(&format!("{}/wp-admin/admin-ajax.php", config.list_url()))
+        .set("Referer", &config.list_url())
         .set("X-Requested-With", "XMLHttpRequest")
+        .idempotent()
         .send_form(form)?
-        .into_string()?;
-
-    let selector = Selector::parse("div.page-item-detail")
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+        .into_string()?)
+}
 
-    parse_manga_list(url, source_id, &body, &selector, false)
+/// The `meta_query` of the listings, if they are filtered on a chapter type.
+fn chapter_type_query(config: &MadaraConfig) -> Vec<(&str, &str)> {
+    match config.chapter_type {
+        Some(chapter_type) => vec![
+            ("vars[meta_query][0][key]", "_wp_manga_chapter_type"),
+            ("vars[meta_query][0][value]", chapter_type.as_str()),
+        ],
+        None => vec![],
+    }
 }
 
-pub fn get_popular_manga(url: &str, source_id: i64, page: i64, client: &Agent) -> Result<Vec<MangaInfo>> {
-    let form: &[(&str, &str)] = &[
+fn get_sorted_manga(
+    config: &MadaraConfig,
+    page_number: i64,
+    meta_key: &str,
+    sidebar: &str,
+    pagination: &Pagination,
+    client: &Agent,
+) -> Result<Vec<MangaInfo>> {
+    let page = (page_number - 1).to_string();
+    let posts_per_page = config.posts_per_page.to_string();
+    let mut form: Vec<(&str, &str)> = vec![
         ("action", "madara_load_more"),
-        ("page", &(page - 1).to_string()),
+        ("page", &page),
         ("template", "madara-core/content/content-archive"),
         ("vars[orderby]", "meta_value_num"),
         ("vars[paged]", "1"),
-        ("vars[posts_per_page]", "20"),
+        ("vars[posts_per_page]", &posts_per_page),
         ("vars[post_type]", "wp-manga"),
         ("vars[post_status]", "publish"),
-        ("vars[meta_key]", "_wp_manga_views"),
+        ("vars[meta_key]", meta_key),
         ("vars[order]", "desc"),
-        ("vars[sidebar]", "full"),
+        ("vars[sidebar]", sidebar),
         ("vars[manga_archives_item_layout]", "big_thumbnail"),
-        ("vars[meta_query][0][key]", "_wp_manga_chapter_type"),
-        ("vars[meta_query][0][value]", "manga"),
     ];
-    
-    let body = client.post(&format!("{}/wp-admin/admin-ajax.php", url))
-        .set("Referer", url)
-        .set("X-Requested-With", "XMLHttpRequest")
-        .send_form(form)?
-        .into_string()?;
+    form.extend(chapter_type_query(config));
 
-    let selector = Selector::parse("div.page-item-detail")
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let body = load_more(config, &form, client)?;
 
-    parse_manga_list(url, source_id, &body, &selector, false)
+    let listing = format!("{} {}", config.list_url(), meta_key);
+
+    read_page(config, pagination, &listing, page_number, &body, config.selectors.manga_item, true)
 }
 
-pub fn search_manga_old(
-    url: &str,
-    source_id: i64,
+pub fn get_latest_manga(
+    config: &MadaraConfig,
     page: i64,
-    query: &str,
-    client: &Agent
+    pagination: &Pagination,
+    client: &Agent,
 ) -> Result<Vec<MangaInfo>> {
-    let body = client.get(&format!("{}/search?q={}&page={}", url, query, page))
-        .call()?
-        .into_string()?;
-
-    let selector =
-        Selector::parse(".manga-item").map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    match config.list_endpoint {
+        ListEndpoint::Ajax => get_sorted_manga(config, page, "_latest_update", "right", pagination, client),
+        ListEndpoint::Paged { latest, .. } => {
+            get_paged_manga(config, latest, page, "", config.selectors.manga_item, pagination, client)
+        }
+    }
+}
 
-    parse_manga_list(url, source_id, &body, &selector, false)
+pub fn get_popular_manga(
+    config: &MadaraConfig,
+    page: i64,
+    pagination: &Pagination,
+    client: &Agent,
+) -> Result<Vec<MangaInfo>> {
+    match config.list_endpoint {
+        ListEndpoint::Ajax => get_sorted_manga(config, page, "_wp_manga_views", "full", pagination, client),
+        ListEndpoint::Paged { popular, .. } => {
+            get_paged_manga(config, popular, page, "", config.selectors.manga_item, pagination, client)
+        }
+    }
 }
 
+/// Searches by `query`, `filters` or both. Sites with their own paged
+/// listings only search by `query`, and find nothing by filters alone.
 pub fn search_manga(
-    url: &str,
-    source_id: i64,
+    config: &MadaraConfig,
     page: i64,
-    query: &str,
-    is_selector_url: bool,
-    client: &Agent
+    query: Option<&str>,
+    filters: Option<&[Input]>,
+    pagination: &Pagination,
+    client: &Agent,
 ) -> Result<Vec<MangaInfo>> {
-    let form: &[(&str, &str)] = &[
-        ("action", "madara_load_more"),
-        ("vars[s]", query),
-        ("template", "madara-core/content/content-search"),
-        ("vars[paged]", "1"),
-        ("vars[template]", "archive"),
-        ("vars[post_type]", "wp-manga"),
-        ("vars[post_status]", "publish"),
-        ("vars[sidebar]", "right"),
-        ("vars[manga_archives_item_layout]", "big_thumbnail"),
-        ("vars[posts_per_page]", "20"),
-        ("vars[meta_query][0][key]", "_wp_manga_chapter_type"),
-        ("vars[meta_query][0][value]", "manga"),
-        ("page", &(page - 1).to_string()),
-    ];
-    
-    let body = client.post(&format!("{}/wp-admin/admin-ajax.php", url))
-        .set("Referer", url)
-        .set("X-Requested-With", "XMLHttpRequest")
-        .send_form(form)?
-        .into_string()?;
-
-    let selector = if is_selector_url {
-        Selector::parse("a").map_err(|e| anyhow!("failed to parse selector: {:?}", e))?
-    } else {
-        Selector::parse("div.c-tabs-item__content")
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?
-    };
-
-    parse_manga_list(url, source_id, &body, &selector, is_selector_url)
-}
+    if query.is_none() && filters.is_none() {
+        bail!("query and filters cannot be both empty")
+    }
 
-pub fn get_manga_detail(url: &str, path: &str, source_id: i64, client: &Agent) -> Result<MangaInfo> {
-    let body = client.get(&format!("{}{}", url, path))
-        .call()?
-        .into_string()?;
+    if let ListEndpoint::Paged { search, .. } = config.list_endpoint {
+        // these listings have no filters, so filters alone find nothing
+        let query = match query {
+            Some(query) => query,
+            None => return Ok(vec![]),
+        };
+        return get_paged_manga(config, search, page, query, config.selectors.search_item, pagination, client);
+    }
 
-    let doc = Html::parse_document(&body);
+    let search = Search::new(query, filters.unwrap_or_default());
 
-    let selector_name =
-        Selector::parse(r#"div.post-title h3, div.post-title h1, div.series-title h1"#)
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let listing = format!("{} {:?}", config.list_url(), search);
 
-    let selector_img = Selector::parse(".summary_image img, .series-img img")
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    if config.search_page {
+        let body = match client.get(&search.page_url(&config.list_url(), page)).call() {
+            Ok(response) => response.into_string()?,
+            Err(e) if page > 1 && pagination::is_missing_page(&e) => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
 
-    let selector_artist = Selector::parse(".artist-content a")
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+        read_page(config, pagination, &listing, page, &body, config.selectors.search_item, false)
+    } else {
+        let page_number = page;
+        let page = (page - 1).to_string();
+        let posts_per_page = config.posts_per_page.to_string();
+        let mut form: Vec<(&str, &str)> = vec![
+            ("action", "madara_load_more"),
+            ("vars[s]", &search.query),
+            ("template", "madara-core/content/content-search"),
+            ("vars[paged]", "1"),
+            ("vars[template]", "archive"),
+            ("vars[post_type]", "wp-manga"),
+            ("vars[post_status]", "publish"),
+            ("vars[sidebar]", "right"),
+            ("vars[manga_archives_item_layout]", "big_thumbnail"),
+            ("vars[posts_per_page]", &posts_per_page),
+        ];
+        let chapter_type = chapter_type_query(config);
+        let vars = search.form_vars(if chapter_type.is_empty() { 0 } else { 1 });
+        form.extend(chapter_type);
+        form.extend(vars.iter().map(|(key, value)| (key.as_str(), value.as_str())));
+        form.push(("page", &page));
+
+        let body = load_more(config, &form, client)?;
+
+        read_page(config, pagination, &listing, page_number, &body, config.selectors.search_item, true)
+    }
+}
 
-    let selector_genre = Selector::parse(r#".genres-content a"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+pub fn get_manga_detail(config: &MadaraConfig, path: &str, client: &Agent) -> Result<MangaInfo> {
+    let body = client.get(&format!("{}{}", config.url(), path))
+        .call()?
+        .into_string()?;
 
-    let selector_desc = Selector::parse("div.description-summary div.summary__content, div.summary_content div.post-content_item > h5 + div, div.summary_content div.manga-excerpt, div.summary-text p")
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    Ok(parse_manga_detail(&body)?.into_manga_info(config, path))
+}
 
-    Ok(MangaInfo {
-        source_id,
-        title: doc
-            .select(&selector_name)
-            .next()
-            .and_then(|item| item.last_child())
-            .and_then(|t| t.value().as_text())
-            .unwrap()
-            .trim()
-            .to_string(),
-        author: doc
-            .select(&selector_artist)
-            .flat_map(|el| el.text())
-            .map(|s| s.to_string())
-            .collect(),
-        genre: doc
-            .select(&selector_genre)
-            .flat_map(|el| el.text())
-            .map(|s| s.to_string())
-            .collect(),
-        status: None,
-        description: Option::from(
-            doc.select(&selector_desc)
-                .flat_map(|el| el.text())
-                .collect::<Vec<&str>>()
-                .join("")
-                .trim()
-                .to_string(),
-        ),
-        path: path.to_string().replace(url, ""),
-        cover_url: doc
-            .select(&selector_img)
-            .find_map(|el| get_data_src(&el))
-            .unwrap_or_default(),
-    })
+/// The upload date in one of the formats of the site, or else in the words
+/// of its locale.
+fn parse_date(config: &MadaraConfig, date: &str) -> Option<NaiveDateTime> {
+    let date = date.trim();
+    config
+        .date_formats
+        .iter()
+        .find_map(|format| {
+            NaiveDateTime::parse_from_str(date, format)
+                .ok()
+                .or_else(|| NaiveDate::parse_from_str(date, format).ok()?.and_hms_opt(0, 0, 0))
+        })
+        .or_else(|| parse_upload_date(date, config.locale))
 }
 
+/// Reads the chapters matched by `selector`, each with the link matched by
+/// `chapter_url` in it.
 fn parse_chapters(
-    url: &str,
+    config: &MadaraConfig,
     doc: &Html,
     selector: &Selector,
     selector_chapter_name: &Selector,
     selector_chapter_time: &Selector,
-    selector_chapter_url: &Selector,
-    source_id: i64,
+    chapter_url: &str,
 ) -> Result<Vec<ChapterInfo>> {
-    let chapters: Vec<ChapterInfo> = doc
-        .select(selector)
+    let selector_chapter_url = parse_selector(chapter_url)?;
+
+    doc.select(selector)
         .map(|el| {
-            let chapter_name = el
-                .select(selector_chapter_name)
-                .flat_map(|el| el.text())
-                .collect::<Vec<&str>>()
-                .join("")
-                .trim()
-                .to_string();
+            let chapter = parse_chapter_title(
+                &el.select(selector_chapter_name)
+                    .flat_map(|el| el.text())
+                    .collect::<String>(),
+            );
             let chapter_time = el
                 .select(selector_chapter_time)
                 .flat_map(|el| el.text())
                 .collect::<Vec<&str>>()
                 .join("");
-            let chapter_time = format!("{} 00:00", chapter_time.trim());
-
-            let uploaded = if let Ok(uploaded) =
-                NaiveDateTime::parse_from_str(&chapter_time, "%B %d, %Y %H:%M")
-            {
-                uploaded
-            } else if let Ok(uploaded) =
-                NaiveDateTime::parse_from_str(&chapter_time, "%d %b %Y %H:%M")
-            {
-                uploaded
-            } else {
-                NaiveDateTime::from_timestamp(0, 0)
-            }
-            .timestamp();
-
-            ChapterInfo {
-                source_id,
-                title: chapter_name.clone(),
-                path: el
-                    .select(selector_chapter_url)
-                    .next()
-                    .unwrap()
-                    .value()
-                    .attr("href")
-                    .unwrap()
-                    .to_string()
-                    .replace(url, ""),
-                number: chapter_name
-                    .replace("Chapter ", "")
-                    .split(' ')
-                    .next()
-                    .and_then(|s| s.parse::<f64>().ok())
-                    .unwrap_or_default(),
+            let uploaded = parse_date(config, &chapter_time)
+                .map(|uploaded| uploaded.timestamp())
+                .unwrap_or_default();
+
+            let path = el
+                .select(&selector_chapter_url)
+                .next()
+                .and_then(|el| el.value().attr("href"))
+                .ok_or_else(|| SourceError::field(chapter_url, "path"))?;
+
+            Ok(ChapterInfo {
+                source_id: config.id(),
+                title: chapter.clean_title(),
+                path: path.replace(config.url(), ""),
+                number: chapter.number.unwrap_or_default(),
                 scanlator: None,
                 uploaded,
-            }
+            })
         })
-        .collect();
+        .collect()
+}
 
-    Ok(chapters)
+pub fn get_chapters(config: &MadaraConfig, path: &str, client: &Agent) -> Result<Vec<ChapterInfo>> {
+    match config.chapter_endpoint {
+        ChapterEndpoint::Ajax => get_chapters_ajax(config, path, client),
+        ChapterEndpoint::Old => get_chapters_old(config, path, client),
+    }
 }
 
-pub fn get_chapters_old(url: &str, path: &str, source_id: i64, client: &Agent) -> Result<Vec<ChapterInfo>> {
-    let body = client.get(&format!("{}{}", url, path))
+fn get_chapters_old(config: &MadaraConfig, path: &str, client: &Agent) -> Result<Vec<ChapterInfo>> {
+    let body = client.get(&format!("{}{}", config.url(), path))
         .call()?
         .into_string()?;
 
     let doc = Html::parse_document(&body);
 
-    let selector = Selector::parse(r#"#chapterlist .a-h.wleft"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector = parse_selector(r#"#chapterlist .a-h.wleft"#)?;
 
-    let selector_chapter_name = Selector::parse(r#".chapter-name"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_chapter_name = parse_selector(config.selectors.chapter_name.unwrap_or(".chapter-name"))?;
 
-    let selector_chapter_time = Selector::parse(r#".chapter-time"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
-
-    let selector_chapter_url = Selector::parse(".chapter-name")
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_chapter_time = parse_selector(r#".chapter-time"#)?;
 
     parse_chapters(
-        url,
+        config,
         &doc,
         &selector,
         &selector_chapter_name,
         &selector_chapter_time,
-        &selector_chapter_url,
-        source_id,
+        ".chapter-name",
     )
 }
 
-pub fn get_chapters(
-    url: &str,
-    path: &str,
-    source_id: i64,
-    chapter_name_selector: Option<&str>,
-    client: &Agent
-) -> Result<Vec<ChapterInfo>> {
-    let body = client.post(&format!("{}{}ajax/chapters", url, path))
-        .set("Referer", url)
+fn get_chapters_ajax(config: &MadaraConfig, path: &str, client: &Agent) -> Result<Vec<ChapterInfo>> {
+    let body = client.post(&format!("{}{}ajax/chapters", config.url(), path))
+        .set("Referer", config.url())
         .set("Content-Length", "0")
         .set("X-Requested-With", "XMLHttpRequest")
+        .idempotent()
         .call()?
         .into_string()?;
 
     let doc = Html::parse_document(&body);
 
-    let selector = Selector::parse("li.wp-manga-chapter,li.chapter-li")
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
-
-    let selector_chapter_name = Selector::parse(chapter_name_selector.unwrap_or("a"))
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector = parse_selector("li.wp-manga-chapter,li.chapter-li")?;
 
-    let selector_chapter_url =
-        Selector::parse("a").map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_chapter_name = parse_selector(config.selectors.chapter_name.unwrap_or("a"))?;
 
-    let selector_chapter_time = Selector::parse(".chapter-release-date")
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_chapter_time = parse_selector(".chapter-release-date")?;
 
     parse_chapters(
-        url,
+        config,
         &doc,
         &selector,
         &selector_chapter_name,
         &selector_chapter_time,
-        &selector_chapter_url,
-        source_id,
+        "a",
     )
 }
 
-pub fn get_pages(url: &str, path: &str, client: &Agent) -> Result<Vec<String>> {
-    let body = client.get(&format!("{}{}", url, path))
+pub fn get_pages(config: &MadaraConfig, path: &str, client: &Agent) -> Result<Vec<String>> {
+    let body = client.get(&format!("{}{}", config.url(), path))
         .call()?
         .into_string()?;
 
-    let doc = Html::parse_document(&body);
+    parse_pages(config, &body)
+}
+
+/// The images of a chapter, decrypted first if the chapter protector hides
+/// them.
+pub fn parse_pages(config: &MadaraConfig, body: &str) -> Result<Vec<String>> {
+    if let Some(pages) = protected_pages(body)? {
+        return Ok(pages);
+    }
 
-    let selector =
-        Selector::parse(r#"div.page-break, li.blocks-gallery-item, reading-content, div.theimage, img"#)
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let doc = Html::parse_document(body);
+
+    let selector = parse_selector(config.selectors.pages)?;
 
     Ok(doc
         .select(&selector)
@@ -398,3 +443,113 @@ pub fn get_pages(url: &str, path: &str, client: &Agent) -> Result<Vec<String>> {
         .map(|p| p.trim().to_string())
         .collect())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tanoshi_lib::prelude::{Lang, SourceInfo};
+
+    fn config() -> MadaraConfig {
+        MadaraConfig::new(SourceInfo {
+            id: 1,
+            name: "Madara".to_string(),
+            url: "https://madara.local".to_string(),
+            version: "0.0.0",
+            icon: "",
+            languages: Lang::Single("en".to_string()),
+            nsfw: false,
+        })
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        let config = config().date_format("%d/%m/%Y");
+
+        assert_eq!(
+            parse_date(&config, " 05/03/2022 "),
+            NaiveDate::from_ymd_opt(2022, 3, 5).and_then(|date| date.and_hms_opt(0, 0, 0))
+        );
+        assert_eq!(
+            parse_date(&config, "March 5, 2022"),
+            NaiveDate::from_ymd_opt(2022, 3, 5).and_then(|date| date.and_hms_opt(0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_search_results_as_links() {
+        let config = config().search_item_selector("a");
+        let body = r#"<a href="https://madara.local/manga/solo/"><div class="post-title"><h3>Solo</h3></div><img src="/cover.jpg"></a>"#;
+
+        let manga = parse_manga_list(&config, body, &parse_selector("a").unwrap()).unwrap();
+        assert_eq!(manga[0].title, "Solo");
+        assert_eq!(manga[0].path, "/manga/solo/");
+        assert_eq!(manga[0].cover_url, "/cover.jpg");
+    }
+
+    #[test]
+    fn test_unexpected_item_is_an_error() {
+        let body = r#"<div class="page-item-detail"><div class="post-title"><h3>No link</h3></div></div>"#;
+
+        let error = parse_manga_list(&config(), body, &parse_selector("div.page-item-detail").unwrap())
+            .unwrap_err();
+        assert!(SourceError::find(&error).unwrap().is_broken());
+    }
+
+    #[test]
+    fn test_paged_search_by_filters_alone() {
+        let config = config().list_endpoint(ListEndpoint::Paged {
+            popular: "/webtoons/{page}",
+            latest: "/webtoons/{page}",
+            search: "/search?q={query}&page={page}",
+        });
+        let client = networking::build_ureq_agent(None, None);
+
+        let manga = search_manga(&config, 1, None, Some(&[]), &Pagination::default(), &client).unwrap();
+        assert!(manga.is_empty());
+        assert!(get_filter_list(&config).is_empty());
+    }
+
+    #[test]
+    fn test_paged_url_encodes_query() {
+        assert_eq!(
+            paged_url(&config(), "/search?q={query}&page={page}", 2, "love & war #1+"),
+            "https://madara.local/search?q=love+%26+war+%231%2B&page=2"
+        );
+    }
+
+    #[test]
+    fn test_pagination_end() {
+        let config = config();
+        let pagination = Pagination::default();
+        let listing = "pagination-test";
+        let selector = config.selectors.manga_item;
+        let read = |page, body: &str| {
+            read_page(&config, &pagination, listing, page, body, selector, true).unwrap()
+        };
+
+        let page_1 = read(1, include_str!("../tests/fixtures/pagination/load_more_page_1.html"));
+        assert_eq!(page_1.len(), 2);
+        assert_eq!(page_1[0].path, "/manga/solo-leveling/");
+
+        let page_2 = read(2, include_str!("../tests/fixtures/pagination/load_more_page_2.html"));
+        assert_eq!(page_2[0].path, "/manga/tower-of-god/");
+
+        // the site starts over with the first page
+        assert!(read(3, include_str!("../tests/fixtures/pagination/load_more_page_3.html")).is_empty());
+        assert!(read(3, include_str!("../tests/fixtures/pagination/load_more_error.html")).is_empty());
+        assert!(read(3, "0").is_empty());
+        assert!(read(3, "\n").is_empty());
+
+        // asking for the first page again is not the end
+        assert_eq!(
+            read(1, include_str!("../tests/fixtures/pagination/load_more_page_1.html")).len(),
+            2
+        );
+
+        // and forgets the pages read before it, which may have shifted since
+        assert_eq!(
+            read(3, include_str!("../tests/fixtures/pagination/load_more_page_2.html")).len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use tanoshi_lib::prelude::MangaInfo;
+
+/// Listings whose pages are remembered, the least recently read dropped first.
+const MAX_LISTINGS: usize = 16;
+
+/// The series each page of the listings read lately started with, to tell
+/// when a site sends the first page again past the end.
+#[derive(Debug, Default)]
+pub struct Pagination {
+    listings: Mutex<VecDeque<(String, HashMap<i64, String>)>>,
+}
+
+impl Pagination {
+    /// `manga` of page `page` of `listing`, or nothing if it starts with the
+    /// series an earlier page started with, as sites that send the first
+    /// page again past the end do. Reading page 1 starts the listing over.
+    pub(crate) fn unseen(&self, listing: &str, page: i64, manga: Vec<MangaInfo>) -> Vec<MangaInfo> {
+        let first = match manga.first() {
+            Some(first) => first.path.clone(),
+            None => return manga,
+        };
+
+        let mut listings = self.listings.lock().unwrap();
+        let mut pages = listings
+            .iter()
+            .position(|(key, _)| key == listing)
+            .and_then(|index| listings.remove(index))
+            .map(|(_, pages)| pages)
+            .unwrap_or_default();
+        if page <= 1 {
+            pages.clear();
+        }
+
+        let repeated = pages
+            .iter()
+            .any(|(earlier, path)| *earlier < page && *path == first);
+        if !repeated {
+            pages.insert(page, first);
+        }
+
+        listings.push_back((listing.to_string(), pages));
+        while listings.len() > MAX_LISTINGS {
+            listings.pop_front();
+        }
+
+        if repeated {
+            vec![]
+        } else {
+            manga
+        }
+    }
+}
+
+/// Whether `body` is how the site says there is no page `page`: nothing at
+/// all, the `0` or `-1` WordPress answers a failed ajax call with, or a whole
+/// error page where `madara_load_more` sends a fragment.
+pub(crate) fn is_end(body: &str, page: i64, fragment: bool) -> bool {
+    let body = body.trim();
+    if body.is_empty() || body == "0" || body == "-1" {
+        return true;
+    }
+
+    let start = body.chars().take(512).collect::<String>().to_ascii_lowercase();
+    fragment && page > 1 && (start.starts_with("<!doctype html") || start.starts_with("<html"))
+}
+
+/// Whether the site answered a page past the end with `404 Not Found`.
+pub(crate) fn is_missing_page(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ureq::Error>(),
+        Some(ureq::Error::Status(404, _))
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn manga(path: &str) -> Vec<MangaInfo> {
+        vec![MangaInfo {
+            source_id: 1,
+            title: path.to_string(),
+            author: vec![],
+            genre: vec![],
+            status: None,
+            description: None,
+            path: path.to_string(),
+            cover_url: String::new(),
+        }]
+    }
+
+    #[test]
+    fn test_least_recent_listing_is_forgotten() {
+        let pagination = Pagination::default();
+        for i in 0..=MAX_LISTINGS {
+            pagination.unseen(&format!("search {}", i), 1, manga("/manga/solo/"));
+        }
+
+        assert_eq!(pagination.listings.lock().unwrap().len(), MAX_LISTINGS);
+        assert!(pagination.unseen("search 1", 2, manga("/manga/solo/")).is_empty());
+        // the first search is gone, so its first page is not known any more
+        assert_eq!(pagination.unseen("search 0", 2, manga("/manga/solo/")).len(), 1);
+    }
+}
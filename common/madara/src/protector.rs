@@ -0,0 +1,158 @@
+//! Pages hidden by the WP Manga Chapter Protector plugin.
+//!
+//! The plugin leaves no `<img>` in the chapter, only a script with the list
+//! of pages encrypted by CryptoJS: AES-256-CBC with the key and IV derived
+//! from `wpmangaprotectornonce` and a salt the way OpenSSL does it.
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use anyhow::{Context, Result};
+use errors::SourceError;
+use md5::{Digest, Md5};
+use serde::Deserialize;
+
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+/// `chapter_data` as CryptoJS serialises it.
+#[derive(Debug, Deserialize)]
+struct ChapterData {
+    /// Base64 of the ciphertext.
+    ct: String,
+    /// Hex of the salt.
+    s: String,
+}
+
+/// The page URLs in the protector script of `body`, or `None` if the
+/// chapter is not protected.
+pub fn protected_pages(body: &str) -> Result<Option<Vec<String>>> {
+    let password = match script_var(body, "wpmangaprotectornonce") {
+        Some(password) => password,
+        None => return Ok(None),
+    };
+    let chapter_data = script_var(body, "chapter_data")
+        .ok_or_else(|| changed("chapter protector without chapter_data"))?;
+    let chapter_data: ChapterData =
+        serde_json::from_str(&chapter_data.replace("\\/", "/").replace("\\\"", "\""))
+            .context(changed("unreadable chapter protector chapter_data"))?;
+
+    let salt = decode_hex(&chapter_data.s)?;
+    let ciphertext = base64::decode(&chapter_data.ct)
+        .context(changed("chapter protector ciphertext is not base64"))?;
+    let plaintext = decrypt(password.as_bytes(), &salt, &ciphertext)?;
+
+    let pages =
+        parse_pages(&plaintext).context(changed("chapter protector pages are not a list"))?;
+
+    Ok(Some(
+        pages.into_iter().map(|page| page.trim().to_string()).collect(),
+    ))
+}
+
+/// The list is JSON, encoded once more as a JSON string.
+fn parse_pages(plaintext: &[u8]) -> serde_json::Result<Vec<String>> {
+    let mut pages: serde_json::Value = serde_json::from_slice(plaintext)?;
+    if let serde_json::Value::String(inner) = &pages {
+        pages = serde_json::from_str(inner)?;
+    }
+    serde_json::from_value(pages)
+}
+
+/// The value of `var name = '...'` in a script of `body`.
+fn script_var<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    let start = body.find(&format!("var {}", name))? + "var ".len() + name.len();
+    let rest = body[start..].trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let rest = &rest[1..];
+    rest.find(quote).map(|end| &rest[..end])
+}
+
+/// `EVP_BytesToKey` with MD5 and one round, as CryptoJS derives the key and
+/// IV of a passphrase.
+fn derive_key_iv(password: &[u8], salt: &[u8]) -> ([u8; 32], [u8; 16]) {
+    let mut derived = Vec::with_capacity(48);
+    let mut block: Vec<u8> = vec![];
+    while derived.len() < 48 {
+        let mut hasher = Md5::new();
+        hasher.update(&block);
+        hasher.update(password);
+        hasher.update(salt);
+        block = hasher.finalize().to_vec();
+        derived.extend_from_slice(&block);
+    }
+
+    let mut key = [0; 32];
+    let mut iv = [0; 16];
+    key.copy_from_slice(&derived[..32]);
+    iv.copy_from_slice(&derived[32..48]);
+    (key, iv)
+}
+
+fn decrypt(password: &[u8], salt: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let (key, iv) = derive_key_iv(password, salt);
+    let mut buffer = ciphertext.to_vec();
+    let plaintext = Aes256CbcDec::new(&key.into(), &iv.into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+        .map_err(|_| changed("chapter protector data does not decrypt with its nonce"))?;
+    Ok(plaintext.to_vec())
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(changed(&format!("odd length hex {} in chapter protector", hex)).into());
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| changed(&format!("bad hex {} in chapter protector", hex)).into())
+        })
+        .collect()
+}
+
+fn changed(what: &str) -> SourceError {
+    SourceError::SiteStructureChanged(what.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROTECTED: &str = include_str!("../tests/fixtures/chapter_protector.html");
+
+    #[test]
+    fn test_protected_pages() {
+        let pages = protected_pages(PROTECTED).unwrap().unwrap();
+
+        assert_eq!(
+            pages,
+            (1..=3)
+                .map(|i| format!(
+                    "https://madara.local/wp-content/uploads/WP-manga/data/manga_6123/chapter-1/{:02}.jpg",
+                    i
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_unprotected_chapter() {
+        let body = r#"<div class="page-break"><img src="https://madara.local/01.jpg"></div>"#;
+        assert_eq!(protected_pages(body).unwrap(), None);
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("00ff1A").unwrap(), vec![0x00, 0xff, 0x1a]);
+        assert!(decode_hex("0g").is_err());
+        let error = decode_hex("aéa").unwrap_err();
+        assert!(SourceError::find(&error).map_or(false, SourceError::is_broken));
+    }
+
+    #[test]
+    fn test_wrong_nonce() {
+        let body = PROTECTED.replace("e0f7c2a9b1", "0000000000");
+        let error = protected_pages(&body).unwrap_err();
+        assert!(SourceError::find(&error).map_or(false, SourceError::is_broken));
+    }
+}
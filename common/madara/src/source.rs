@@ -0,0 +1,199 @@
+use std::{collections::HashMap, env};
+
+use anyhow::Result;
+use networking::{build_flaresolverr_client, build_ureq_agent, image_headers, proxy_input, Agent};
+use tanoshi_lib::prelude::{ChapterInfo, Extension, Input, InputType, MangaInfo, SourceInfo};
+
+use crate::{MadaraConfig, Pagination};
+
+const LANGUAGE_INPUT: &str = "Language";
+
+/// Which of the languages of the site to list.
+fn language_input(config: &MadaraConfig) -> Input {
+    Input::Select {
+        name: LANGUAGE_INPUT.to_string(),
+        values: config
+            .languages
+            .iter()
+            .map(|(language, _)| InputType::String(language.to_string()))
+            .collect(),
+        state: Some(config.selected_language as i64),
+    }
+}
+
+/// A Madara site as a whole extension, described by its [`MadaraConfig`].
+pub struct MadaraSource {
+    config: MadaraConfig,
+    preferences: Vec<Input>,
+    pagination: Pagination,
+    client: Agent,
+}
+
+impl MadaraSource {
+    pub fn new(config: MadaraConfig) -> Self {
+        let mut client = build_ureq_agent(None, None);
+
+        // If flaresolverr_url is set, build the client with it
+        if config.flaresolverr {
+            if let Ok(flaresolverr_url) = env::var("FLARESOLVERR_URL") {
+                client = build_flaresolverr_client(config.url(), &flaresolverr_url);
+            }
+        }
+
+        client = client
+            .with_source(&config.source.name)
+            .with_referer(config.url());
+        if config.flaresolverr {
+            client = client.with_cookie_jar_for(config.id());
+        }
+
+        let mut preferences = vec![proxy_input()];
+        if config.languages.len() > 1 {
+            preferences.push(language_input(&config));
+        }
+
+        Self {
+            config,
+            preferences,
+            pagination: Pagination::default(),
+            client,
+        }
+    }
+
+    pub fn config(&self) -> &MadaraConfig {
+        &self.config
+    }
+
+    pub fn client(&self) -> &Agent {
+        &self.client
+    }
+
+    /// Replaces the agent, such as with one that replays fixtures in tests.
+    pub fn with_client(mut self, client: Agent) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+impl Extension for MadaraSource {
+    fn set_preferences(&mut self, preferences: Vec<Input>) -> Result<()> {
+        for input in preferences {
+            for pref in self.preferences.iter_mut() {
+                if input.eq(pref) {
+                    *pref = input.clone();
+                }
+            }
+        }
+
+        self.client.apply_preferences(&self.preferences)?;
+
+        for pref in &self.preferences {
+            if let Input::Select {
+                name,
+                state: Some(state),
+                ..
+            } = pref
+            {
+                let state = *state as usize;
+                if name == LANGUAGE_INPUT && state < self.config.languages.len() {
+                    self.config.selected_language = state;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_preferences(&self) -> Result<Vec<Input>> {
+        Ok(self.preferences.clone())
+    }
+
+    fn get_source_info(&self) -> SourceInfo {
+        self.config.source_info()
+    }
+
+    fn headers(&self) -> HashMap<String, String> {
+        image_headers(&self.client)
+    }
+
+    fn filter_list(&self) -> Vec<Input> {
+        crate::get_filter_list(&self.config)
+    }
+
+    fn get_popular_manga(&self, page: i64) -> Result<Vec<MangaInfo>> {
+        crate::get_popular_manga(&self.config, page, &self.pagination, &self.client)
+    }
+
+    fn get_latest_manga(&self, page: i64) -> Result<Vec<MangaInfo>> {
+        crate::get_latest_manga(&self.config, page, &self.pagination, &self.client)
+    }
+
+    fn search_manga(
+        &self,
+        page: i64,
+        query: Option<String>,
+        filters: Option<Vec<Input>>,
+    ) -> Result<Vec<MangaInfo>> {
+        crate::search_manga(
+            &self.config,
+            page,
+            query.as_deref(),
+            filters.as_deref(),
+            &self.pagination,
+            &self.client,
+        )
+    }
+
+    fn get_manga_detail(&self, path: String) -> Result<MangaInfo> {
+        crate::get_manga_detail(&self.config, &path, &self.client)
+    }
+
+    fn get_chapters(&self, path: String) -> Result<Vec<ChapterInfo>> {
+        crate::get_chapters(&self.config, &path, &self.client)
+    }
+
+    fn get_pages(&self, path: String) -> Result<Vec<String>> {
+        crate::get_pages(&self.config, &path, &self.client)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ChapterType;
+    use tanoshi_lib::prelude::Lang;
+
+    #[test]
+    fn test_language_preference() {
+        let mut source = MadaraSource::new(
+            MadaraConfig::new(SourceInfo {
+                id: 1,
+                name: "Madara".to_string(),
+                url: "https://madara.local".to_string(),
+                version: "0.0.0",
+                icon: "",
+                languages: Lang::Single("en".to_string()),
+                nsfw: false,
+            })
+            .chapter_type(Some(ChapterType::Text))
+            .language("en", "")
+            .language("es", "/es/"),
+        );
+
+        match source.get_source_info().languages {
+            Lang::Multi(languages) => assert_eq!(languages, vec!["en", "es"]),
+            languages => panic!("expected both languages, got {:?}", languages),
+        }
+        assert_eq!(source.config().list_url(), "https://madara.local");
+
+        source
+            .set_preferences(vec![Input::Select {
+                name: LANGUAGE_INPUT.to_string(),
+                values: vec![],
+                state: Some(1),
+            }])
+            .unwrap();
+        assert_eq!(source.config().list_url(), "https://madara.local/es");
+        assert_eq!(source.config().chapter_type.map(|t| t.as_str()), Some("text"));
+    }
+}
@@ -1,6 +1,7 @@
-use anyhow::{anyhow, Result};
-use chrono::NaiveDateTime;
-use fancy_regex::Regex;
+use anyhow::Result;
+use errors::parse_selector;
+use chapterparse::parse_chapter_title;
+use dateparse::{parse_upload_date, Locale};
 use scraper::{ElementRef, Html, Selector};
 use tanoshi_lib::prelude::{ChapterInfo, MangaInfo};
 use networking::Agent;
@@ -35,8 +36,7 @@ fn get_cover(el: &ElementRef, selector: &str) -> Option<String> {
 
 pub fn parse_manga_list(source_id: i64, body: &str, selector: &str) -> Result<Vec<MangaInfo>> {
     let doc = Html::parse_document(body);
-    let selector =
-        Selector::parse(selector).map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector = parse_selector(selector)?;
 
     let mut manga = vec![];
     for el in doc.select(&selector) {
@@ -61,8 +61,7 @@ pub fn parse_search_manga_list(
     selector: &str,
 ) -> Result<Vec<MangaInfo>> {
     let doc = Html::parse_document(body);
-    let selector =
-        Selector::parse(selector).map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector = parse_selector(selector)?;
 
     let mut manga = vec![];
     for el in doc.select(&selector) {
@@ -88,19 +87,13 @@ pub fn get_manga_detail(path: &str, source_id: i64, client: &Agent) -> Result<Ma
 
     let doc = Html::parse_document(&body);
 
-    let selector_img = Selector::parse(r#"span.info-image > img.img-loading"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_img = parse_selector(r#"span.info-image > img.img-loading"#)?;
 
-    let selector_artist =
-        Selector::parse(r#".story-info-right a[href^="https://manganato.com/author"]"#)
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_artist = parse_selector(r#".story-info-right a[href^="https://manganato.com/author"]"#)?;
 
-    let selector_genre =
-        Selector::parse(r#".story-info-right a[href^="https://manganato.com/genre"]"#)
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_genre = parse_selector(r#".story-info-right a[href^="https://manganato.com/genre"]"#)?;
 
-    let selector_desc = Selector::parse(r#"div#panel-story-info-description"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_desc = parse_selector(r#"div#panel-story-info-description"#)?;
 
     Ok(MangaInfo {
         source_id,
@@ -143,25 +136,20 @@ pub fn get_chapters(path: &str, source_id: i64, client: &Agent) -> Result<Vec<Ch
 
     let doc = Html::parse_document(&body);
 
-    let selector = Selector::parse(r#"ul.row-content-chapter > li"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector = parse_selector(r#"ul.row-content-chapter > li"#)?;
 
-    let selector_chapter_name = Selector::parse(r#"a.chapter-name"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector_chapter_name = parse_selector(r#"a.chapter-name"#)?;
 
-    let selector_chapter_time = Selector::parse(r#"span.chapter-time"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
-
-    let chapter_re = Regex::new(r#".*Chapter (\d+\.*\d*).*"#)?;
+    let selector_chapter_time = parse_selector(r#"span.chapter-time"#)?;
 
     let mut chapters = vec![];
 
     for el in doc.select(&selector) {
-        let chapter_name = el
-            .select(&selector_chapter_name)
-            .flat_map(|el| el.text())
-            .collect::<Vec<&str>>()
-            .join("");
+        let chapter = parse_chapter_title(
+            &el.select(&selector_chapter_name)
+                .flat_map(|el| el.text())
+                .collect::<String>(),
+        );
         let chapter_time = el
             .select(&selector_chapter_time)
             .next()
@@ -169,15 +157,9 @@ pub fn get_chapters(path: &str, source_id: i64, client: &Agent) -> Result<Vec<Ch
             .map(|title| title.to_string())
             .unwrap_or_else(|| "".to_string());
 
-        let number = chapter_re
-            .captures(&chapter_name)?
-            .and_then(|cap| cap.get(1))
-            .map(|num| num.as_str().to_string())
-            .unwrap_or_default();
-
         chapters.push(ChapterInfo {
             source_id,
-            title: chapter_name.clone(),
+            title: chapter.clean_title(),
             path: el
                 .select(&selector_chapter_name)
                 .map(|el| {
@@ -189,11 +171,11 @@ pub fn get_chapters(path: &str, source_id: i64, client: &Agent) -> Result<Vec<Ch
                 .flatten()
                 .collect::<Vec<String>>()
                 .join(""),
-            number: number.parse().unwrap_or_default(),
+            number: chapter.number.unwrap_or_default(),
             scanlator: None,
-            uploaded: NaiveDateTime::parse_from_str(&chapter_time, "%b %d,%Y %H:%M")
-                .unwrap_or_else(|_| NaiveDateTime::from_timestamp(0, 0))
-                .timestamp(),
+            uploaded: parse_upload_date(&chapter_time, Locale::En)
+                .map(|uploaded| uploaded.timestamp())
+                .unwrap_or_default(),
         });
     }
 
@@ -203,8 +185,7 @@ pub fn get_chapters(path: &str, source_id: i64, client: &Agent) -> Result<Vec<Ch
 pub fn get_pages(body: &str) -> Result<Vec<String>> {
     let doc = Html::parse_document(&body);
 
-    let selector = Selector::parse(r#".container-chapter-reader > img"#)
-        .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
+    let selector = parse_selector(r#".container-chapter-reader > img"#)?;
 
     Ok(doc
         .select(&selector)
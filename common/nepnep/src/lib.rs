@@ -2,9 +2,10 @@ mod dto;
 
 use std::collections::HashSet;
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{bail, Result};
+use errors::{parse_selector, SourceError};
 use fancy_regex::Regex;
-use scraper::{Html, Selector};
+use scraper::Html;
 use tanoshi_lib::prelude::{ChapterInfo, Input, InputType, MangaInfo, TriState};
 use networking::Agent;
 
@@ -222,11 +223,11 @@ pub fn get_all_manga(url: &str, client: &Agent) -> Result<Vec<Dir>> {
         .into_string()?;
     let start_index = html
         .find("vm.Directory =")
-        .ok_or_else(|| anyhow!("vm.Directory not found"))?;
+        .ok_or_else(|| SourceError::SiteStructureChanged("vm.Directory not found".to_string()))?;
     let dir = &html[start_index + 15..];
     let end_index = dir
         .find("}];")
-        .ok_or_else(|| anyhow!("vm.Directory not found"))?;
+        .ok_or_else(|| SourceError::SiteStructureChanged("vm.Directory not found".to_string()))?;
     let vm_dir = dir[..end_index + 2].to_string();
     Ok(serde_json::from_str::<Vec<Dir>>(&vm_dir)?)
 }
@@ -461,48 +462,30 @@ pub fn get_manga_detail(source_id: i64, url: &str, path: String, client: &Agent)
     let doc = Html::parse_document(&body);
 
     let title = doc
-        .select(
-            &Selector::parse("li[class=\"list-group-item d-none d-sm-block\"] h1")
-                .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?,
-        )
+        .select(&parse_selector("li[class=\"list-group-item d-none d-sm-block\"] h1")?)
         .next()
         .and_then(|el| el.text().next().map(|e| e.to_string()))
         .unwrap_or_default();
     let description = doc
-        .select(
-            &Selector::parse("div[class=\"top-5 Content\"]")
-                .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?,
-        )
+        .select(&parse_selector("div[class=\"top-5 Content\"]")?)
         .next()
         .and_then(|el| el.text().next().map(|e| e.to_string()));
     let author = doc
-        .select(
-            &Selector::parse("a[href^=\"/search/?author=\"]")
-                .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?,
-        )
+        .select(&parse_selector("a[href^=\"/search/?author=\"]")?)
         .next()
         .map(|el| el.text().into_iter().map(|s| s.to_string()).collect())
         .unwrap_or_else(Vec::new);
     let genre = doc
-        .select(
-            &Selector::parse("a[href^=\"/search/?genre=\"]")
-                .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?,
-        )
+        .select(&parse_selector("a[href^=\"/search/?genre=\"]")?)
         .next()
         .map(|el| el.text().into_iter().map(|s| s.to_string()).collect())
         .unwrap_or_else(Vec::new);
     let status = doc
-        .select(
-            &Selector::parse("a[href^=\"/search/?status=\"]")
-                .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?,
-        )
+        .select(&parse_selector("a[href^=\"/search/?status=\"]")?)
         .next()
         .and_then(|el| el.text().next().map(|s| s.replace("/search/?status=", "")));
     let cover_url = doc
-        .select(
-            &Selector::parse("img[class=\"img-fluid bottom-5\"]")
-                .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?,
-        )
+        .select(&parse_selector("img[class=\"img-fluid bottom-5\"]")?)
         .next()
         .and_then(|el| el.value().attr("src").map(str::to_string))
         .unwrap_or_default();
@@ -522,7 +505,7 @@ pub fn get_manga_detail(source_id: i64, url: &str, path: String, client: &Agent)
 fn get_index_name(body: &str) -> Result<String> {
     Ok(Regex::new(r#"(?<=vm\.IndexName = ").*(?=";)"#)?
         .find(body)?
-        .ok_or_else(|| anyhow!("regext not found anything"))?
+        .ok_or_else(|| SourceError::SiteStructureChanged("vm.IndexName not found".to_string()))?
         .as_str()
         .to_string())
 }
@@ -530,7 +513,7 @@ fn get_index_name(body: &str) -> Result<String> {
 fn get_vm_dir(body: &str) -> Result<String> {
     Ok(Regex::new(r#"(?<=vm\.Chapters = )\[.*\](?=;)"#)?
         .find(body)?
-        .ok_or_else(|| anyhow!("regext not found anything"))?
+        .ok_or_else(|| SourceError::SiteStructureChanged("vm.Chapters not found".to_string()))?
         .as_str()
         .to_string())
 }
@@ -598,14 +581,14 @@ pub fn get_pages(url: &str, path: String, client: &Agent) -> Result<Vec<String>>
     let cur_chapter = {
         let mat = Regex::new(r"(?<=vm\.CurChapter = ){.*}(?=;)")?
             .find(&body)?
-            .ok_or_else(|| anyhow!("regext not found anything"))?
+            .ok_or_else(|| SourceError::SiteStructureChanged("vm.CurChapter not found".to_string()))?
             .as_str()
             .to_string();
         serde_json::from_str::<CurChapter>(&mat)?
     };
     let cur_path_name = Regex::new(r#"(?<=vm\.CurPathName = ").*(?=";)"#)?
         .find(&body)?
-        .ok_or_else(|| anyhow!("regext not found anything"))?
+        .ok_or_else(|| SourceError::SiteStructureChanged("vm.CurPathName not found".to_string()))?
         .as_str()
         .to_string();
     let directory = {
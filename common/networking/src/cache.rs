@@ -0,0 +1,281 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use ureq::serde_json;
+use url::Url;
+
+use crate::response::{BufferedResponse, Received};
+
+/// Directory the response caches of all sources are kept in, the system temp
+/// directory if unset.
+const CACHE_DIR: &str = "CACHE_DIR";
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct CacheEntry {
+    /// Seconds since the epoch when the response was fetched or revalidated.
+    stored_at: u64,
+    response: BufferedResponse,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        now().saturating_sub(self.stored_at) < ttl.as_secs()
+    }
+
+    /// Headers that ask the site to answer 304 if the response is unchanged.
+    fn validators(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![];
+        if let Some(etag) = self.response.header("ETag") {
+            headers.push(("If-None-Match", etag.to_string()));
+        }
+        if let Some(last_modified) = self.response.header("Last-Modified") {
+            headers.push(("If-Modified-Since", last_modified.to_string()));
+        }
+        headers
+    }
+}
+
+/// Successful responses kept on disk, for pages that are expensive to fetch
+/// and rarely change, such as the full catalog of a site.
+///
+/// Only urls whose path starts with one of the routes are cached, each for
+/// the route's TTL. Once that passes, the response is revalidated with its
+/// `ETag` or `Last-Modified` and only downloaded again if it changed.
+pub struct ResponseCache {
+    dir: PathBuf,
+    routes: Vec<(String, Duration)>,
+}
+
+impl ResponseCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            routes: vec![],
+        }
+    }
+
+    /// The cache of `source_id` in `CACHE_DIR`.
+    pub fn for_source(source_id: i64) -> Self {
+        let dir = env::var_os(CACHE_DIR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| env::temp_dir().join("tanoshi-extensions-cache"));
+        Self::new(dir.join(source_id.to_string()))
+    }
+
+    /// Caches responses to urls under `path` for `ttl`, matching whole path
+    /// segments so `/search` covers `/search/1` but not `/searchfoo`. The
+    /// longest matching route wins.
+    pub fn route(mut self, path: &str, ttl: Duration) -> Self {
+        self.routes.push((path.to_string(), ttl));
+        self.routes
+            .sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+        self
+    }
+
+    fn ttl(&self, url: &str) -> Option<Duration> {
+        let url = Url::parse(url).ok()?;
+        self.routes
+            .iter()
+            .find(|(path, _)| is_under(url.path(), path))
+            .map(|(_, ttl)| *ttl)
+    }
+
+    fn path(&self, method: &str, url: &str, body: Option<&str>) -> PathBuf {
+        let key = fnv1a(&[&method.to_ascii_uppercase(), url, body.unwrap_or_default()]);
+        self.dir.join(format!("{:016x}.json", key))
+    }
+
+    fn load(&self, path: &Path) -> Option<CacheEntry> {
+        let content = fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("ignoring broken cache entry {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn store(&self, path: &Path, entry: &CacheEntry) {
+        let result = fs::create_dir_all(&self.dir)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(serde_json::to_string(entry)?))
+            .and_then(|content| {
+                let tmp = path.with_extension("json.tmp");
+                fs::write(&tmp, content)?;
+                Ok(fs::rename(&tmp, path)?)
+            });
+        if let Err(e) = result {
+            log::warn!("failed to write cache entry {}: {}", path.display(), e);
+        }
+    }
+
+    /// Answers from the cache while the entry is fresh, and otherwise calls
+    /// `send` with the headers that revalidate it.
+    pub(crate) fn fetch<F>(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+        send: F,
+    ) -> Result<Received>
+    where
+        F: FnOnce(Vec<(&'static str, String)>) -> Result<Received>,
+    {
+        let ttl = match self.ttl(url) {
+            Some(ttl) => ttl,
+            None => return send(vec![]),
+        };
+
+        let path = self.path(method, url, body);
+        let entry = self.load(&path);
+        if let Some(entry) = &entry {
+            if entry.is_fresh(ttl) {
+                log::debug!("cache hit for {} {}", method, url);
+                return entry.response.clone().into_received();
+            }
+        }
+
+        let validators = entry
+            .as_ref()
+            .map(CacheEntry::validators)
+            .unwrap_or_default();
+        let received = send(validators)?;
+        match (received.response.status(), entry) {
+            (304, Some(mut entry)) => {
+                log::debug!("{} {} not modified", method, url);
+                entry.stored_at = now();
+                self.store(&path, &entry);
+                entry.response.into_received()
+            }
+            (200, _) => {
+                let entry = CacheEntry {
+                    stored_at: now(),
+                    response: BufferedResponse::read(received.response)?,
+                };
+                self.store(&path, &entry);
+                entry.response.into_received()
+            }
+            _ => Ok(received),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// FNV-1a, which unlike the std hasher gives the same names on every
+/// toolchain.
+pub(crate) fn fnv1a(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+fn is_under(path: &str, route: &str) -> bool {
+    match path.strip_prefix(route) {
+        Some(rest) => rest.is_empty() || route.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        build_ureq_agent,
+        test_server::{response, serve},
+    };
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("networking-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_fresh_entry_is_served_from_cache() {
+        let server = serve(vec![response(200, &[], "directory")]);
+        let agent = build_ureq_agent(None, None).with_cache(
+            ResponseCache::new(cache_dir("fresh")).route("/search", Duration::from_secs(60)),
+        );
+
+        for _ in 0..3 {
+            let body = agent
+                .get(&format!("{}/search", server.url))
+                .call()
+                .unwrap()
+                .into_string()
+                .unwrap();
+            assert_eq!(body, "directory");
+        }
+
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_route_matches_whole_segments() {
+        let cache = ResponseCache::new(cache_dir("routes"))
+            .route("/search", Duration::from_secs(60))
+            .route("/api/", Duration::from_secs(30));
+
+        assert_eq!(cache.ttl("https://example.com/search"), Some(Duration::from_secs(60)));
+        assert_eq!(cache.ttl("https://example.com/search?q=a"), Some(Duration::from_secs(60)));
+        assert_eq!(cache.ttl("https://example.com/search/2"), Some(Duration::from_secs(60)));
+        assert_eq!(cache.ttl("https://example.com/searchfoo"), None);
+        assert_eq!(cache.ttl("https://example.com/api/series"), Some(Duration::from_secs(30)));
+        assert_eq!(cache.ttl("https://example.com/apis"), None);
+    }
+
+    #[test]
+    fn test_stale_entry_is_revalidated() {
+        let server = serve(vec![
+            response(
+                200,
+                &[
+                    ("ETag", "\"v1\""),
+                    ("Last-Modified", "Sat, 01 Jan 2022 00:00:00 GMT"),
+                ],
+                "directory",
+            ),
+            response(304, &[], ""),
+            response(200, &[], "uncached"),
+        ]);
+        let agent = build_ureq_agent(None, None)
+            .with_cache(ResponseCache::new(cache_dir("stale")).route("/search", Duration::ZERO));
+
+        let url = format!("{}/search", server.url);
+        agent.get(&url).call().unwrap();
+        let body = agent.get(&url).call().unwrap().into_string().unwrap();
+        assert_eq!(body, "directory");
+
+        let body = agent
+            .get(&format!("{}/manga", server.url))
+            .call()
+            .unwrap()
+            .into_string()
+            .unwrap();
+        assert_eq!(body, "uncached");
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("If-None-Match"), None);
+        assert_eq!(requests[1].header("If-None-Match"), Some("\"v1\""));
+        assert_eq!(
+            requests[1].header("If-Modified-Since"),
+            Some("Sat, 01 Jan 2022 00:00:00 GMT")
+        );
+    }
+}
@@ -0,0 +1,953 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use cookie_store::CookieStore;
+use errors::SourceError;
+use tanoshi_lib::prelude::Input;
+use ureq::{serde_json, Response};
+use url::{form_urlencoded, Url};
+
+#[cfg(any(test, feature = "fixtures"))]
+use crate::fixtures::{FixtureMode, Fixtures};
+use crate::{
+    build_inner_agent,
+    cache::ResponseCache,
+    config::AgentConfig,
+    cookies::{
+        cookie_header, cookie_jar_path, copy_store, has_clearance, insert_cookie, CookieJar,
+    },
+    flaresolverr::{
+        convert_flaresolverr_cookies_to_ureq_cookies, is_challenge, solution_response,
+        FlareSolverr, FlareSolverrMode, FlareSolverrSolution,
+    },
+    metrics::{MetricsSnapshot, SourceMetrics},
+    profile::BrowserProfile,
+    proxy::ProxyConfig,
+    rate_limit::RateLimiter,
+    response::{BufferedResponse, Received},
+    retry::{is_idempotent, retry_after, RetryPolicy},
+};
+
+/// HTTP client used by every extension.
+///
+/// Thin wrapper around [`ureq::Agent`] with the same request API, which
+/// waits for the host's rate limit before every request and retries failed
+/// requests according to its [`RetryPolicy`]. Cloning is cheap and clones
+/// share connections, cookies and rate limits.
+///
+/// With [`FlareSolverr`] attached, the site's challenge is solved before the
+/// first request, and a Cloudflare challenge on any later request is solved
+/// again and the request retried once with the new clearance. Should the site
+/// challenge the retry as well, the page FlareSolverr loaded is returned
+/// instead. If FlareSolverr cannot be reached, requests go out without
+/// clearance until it can.
+///
+/// With a cookie jar, cookies are saved to disk after every request and
+/// loaded again by the next agent using the same jar.
+///
+/// With a proxy, every request and every FlareSolverr solve goes out through
+/// it, so the clearance is issued to the address that uses it.
+#[derive(Clone)]
+pub struct Agent {
+    inner: Arc<RwLock<Inner>>,
+    retry_policy: RetryPolicy,
+    rate_limiter: RateLimiter,
+    flaresolverr: Option<Arc<FlareSolverr>>,
+    pending_solve: Arc<Mutex<Option<String>>>,
+    cookie_jar: Option<Arc<CookieJar>>,
+    cache: Option<Arc<ResponseCache>>,
+    source: Option<Arc<str>>,
+    referer: Option<Arc<str>>,
+    metrics: Option<Arc<SourceMetrics>>,
+    #[cfg(any(test, feature = "fixtures"))]
+    fixtures: Option<Arc<Fixtures>>,
+}
+
+/// The ureq agent, which is replaced whenever cookies, the browser profile,
+/// the config or the proxy change outside of a request.
+struct Inner {
+    agent: ureq::Agent,
+    profile: BrowserProfile,
+    /// Whether the profile was chosen on purpose, by the extension or by a
+    /// solved challenge, rather than being the default.
+    pinned: bool,
+    config: AgentConfig,
+    proxy: Option<ProxyConfig>,
+}
+
+impl Inner {
+    fn rebuild(&mut self, store: CookieStore) {
+        self.agent = build_inner_agent(
+            Some(&self.profile.user_agent),
+            Some(store),
+            &self.config,
+            self.proxy.as_ref(),
+        );
+    }
+
+    fn set_profile(&mut self, profile: BrowserProfile, pinned: bool) {
+        self.profile = profile;
+        self.pinned = pinned;
+        let store = copy_store(&self.agent.cookie_store());
+        self.rebuild(store);
+    }
+}
+
+impl Agent {
+    /// Wraps an agent built elsewhere. Its settings are only known to ureq, so
+    /// [`Agent::config`] reports the defaults until [`Agent::with_config`].
+    pub fn new(inner: ureq::Agent) -> Self {
+        Self::from_parts(
+            inner,
+            BrowserProfile::default(),
+            false,
+            AgentConfig::default(),
+            None,
+        )
+    }
+
+    pub(crate) fn from_parts(
+        inner: ureq::Agent,
+        profile: BrowserProfile,
+        pinned: bool,
+        config: AgentConfig,
+        proxy: Option<ProxyConfig>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Inner {
+                agent: inner,
+                profile,
+                pinned,
+                config,
+                proxy,
+            })),
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: RateLimiter::default(),
+            flaresolverr: None,
+            pending_solve: Arc::new(Mutex::new(None)),
+            cookie_jar: None,
+            cache: None,
+            source: None,
+            referer: None,
+            metrics: None,
+            #[cfg(any(test, feature = "fixtures"))]
+            fixtures: None,
+        }
+    }
+
+    /// Solves challenges with `flaresolverr`, starting with the one in front
+    /// of `url` when the first request is made.
+    pub fn with_flaresolverr(mut self, flaresolverr: FlareSolverr, url: &str) -> Self {
+        let proxy = self.proxy();
+        self.flaresolverr = Some(Arc::new(flaresolverr.with_proxy(proxy)));
+        self.pending_solve = Arc::new(Mutex::new(Some(url.to_string())));
+        self
+    }
+
+    /// Loads the cookies saved at `path` and keeps saving them there.
+    pub fn with_cookie_jar(mut self, path: impl Into<PathBuf>) -> Self {
+        let jar = CookieJar::new(path.into());
+        match jar.load() {
+            Ok(Some((cookies, user_agent))) => {
+                let mut inner = self.inner.write().unwrap();
+                let mut store = copy_store(&inner.agent.cookie_store());
+                for cookie in cookies {
+                    insert_cookie(&mut store, cookie);
+                }
+                if let Some(user_agent) = user_agent {
+                    inner.profile = BrowserProfile::from_user_agent(&user_agent);
+                    inner.pinned = true;
+                }
+                inner.rebuild(store);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!(
+                "failed to load cookie jar {}, starting without it: {}",
+                jar.path().display(),
+                e
+            ),
+        }
+
+        self.cookie_jar = Some(Arc::new(jar));
+        self
+    }
+
+    /// Keeps the cookies of `source_id` in `COOKIE_JAR_DIR`, if it is set.
+    pub fn with_cookie_jar_for(self, source_id: i64) -> Self {
+        match cookie_jar_path(source_id) {
+            Some(path) => self.with_cookie_jar(path),
+            None => self,
+        }
+    }
+
+    /// Names the source in the request logs and counts its requests in
+    /// [`source_metrics`](crate::source_metrics). Unless the user agent was
+    /// chosen already, the agent also takes on the source's
+    /// [`BrowserProfile::for_source`].
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(Arc::from(source));
+        self.metrics = Some(SourceMetrics::for_source(source));
+        {
+            let mut inner = self.inner.write().unwrap();
+            if !inner.pinned {
+                inner.set_profile(BrowserProfile::for_source(source), false);
+            }
+        }
+        self
+    }
+
+    /// Page the images of the source are loaded from, sent as `Referer` by
+    /// [`fetch_image`](crate::fetch_image) and [`image_headers`](crate::image_headers).
+    pub fn with_referer(mut self, referer: &str) -> Self {
+        self.referer = Some(Arc::from(referer));
+        self
+    }
+
+    pub fn referer(&self) -> Option<&str> {
+        self.referer.as_deref()
+    }
+
+    /// Sends the user agent and headers of `profile` with every request.
+    pub fn with_profile(self, profile: BrowserProfile) -> Self {
+        self.inner.write().unwrap().set_profile(profile, true);
+        self
+    }
+
+    pub fn profile(&self) -> BrowserProfile {
+        self.inner.read().unwrap().profile.clone()
+    }
+
+    /// The counters of the source set with [`Agent::with_source`].
+    pub fn metrics(&self) -> Option<MetricsSnapshot> {
+        self.metrics.as_ref().map(|metrics| metrics.snapshot())
+    }
+
+    /// Answers the requests to the routes of `cache` from it.
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Replaces the timeouts, connection limits and redirect limit of every
+    /// clone of this agent.
+    pub fn with_config(self, config: AgentConfig) -> Self {
+        {
+            let mut inner = self.inner.write().unwrap();
+            inner.config = config;
+            let store = copy_store(&inner.agent.cookie_store());
+            inner.rebuild(store);
+        }
+        self
+    }
+
+    pub fn config(&self) -> AgentConfig {
+        self.inner.read().unwrap().config.clone()
+    }
+
+    /// Sends every request through `proxy`, or directly if it is `None`.
+    pub fn with_proxy(self, proxy: Option<ProxyConfig>) -> Self {
+        self.set_proxy(proxy);
+        self
+    }
+
+    /// Switches every clone of this agent, and its FlareSolverr, to `proxy`.
+    pub fn set_proxy(&self, proxy: Option<ProxyConfig>) {
+        let mut inner = self.inner.write().unwrap();
+        if inner.proxy == proxy {
+            return;
+        }
+
+        match &proxy {
+            Some(proxy) => log::info!("sending requests through proxy {}", proxy),
+            None => log::info!("sending requests without proxy"),
+        }
+        inner.proxy.clone_from(&proxy);
+        let store = copy_store(&inner.agent.cookie_store());
+        inner.rebuild(store);
+        drop(inner);
+
+        if let Some(flaresolverr) = &self.flaresolverr {
+            flaresolverr.set_proxy(proxy);
+        }
+    }
+
+    pub fn proxy(&self) -> Option<ProxyConfig> {
+        self.inner.read().unwrap().proxy.clone()
+    }
+
+    /// Applies the settings a source keeps in its preferences, currently the
+    /// [`proxy_input`](crate::proxy_input).
+    pub fn apply_preferences(&self, preferences: &[Input]) -> Result<()> {
+        self.set_proxy(ProxyConfig::from_preferences(preferences)?);
+        Ok(())
+    }
+
+    /// Records responses to, or replays them from, the fixtures in `dir` as
+    /// `NETWORKING_FIXTURES` says. Meant for tests, see [`fixtures_dir`](crate::fixtures_dir).
+    #[cfg(any(test, feature = "fixtures"))]
+    pub fn with_fixtures(self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let mode = FixtureMode::from_env(&dir).unwrap_or_else(|e| panic!("{}", e));
+        self.with_fixture_mode(dir, mode)
+    }
+
+    #[cfg(any(test, feature = "fixtures"))]
+    pub fn with_fixture_mode(mut self, dir: impl Into<PathBuf>, mode: FixtureMode) -> Self {
+        self.fixtures = match mode {
+            FixtureMode::Live => None,
+            mode => Some(Arc::new(Fixtures::new(dir.into(), mode))),
+        };
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Allows at most `permits` requests to `host` and its subdomains per
+    /// `period`. The limit is shared with every other agent of the extension
+    /// that limits the same host.
+    pub fn with_rate_limit(mut self, host: &str, permits: u32, period: Duration) -> Self {
+        self.rate_limiter.add(host, permits, period);
+        self
+    }
+
+    pub fn request(&self, method: &str, url: &str) -> Request {
+        Request {
+            agent: self.clone(),
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: vec![],
+            query: vec![],
+            timeout: None,
+            idempotent: is_idempotent(method),
+            cache_hit: false,
+        }
+    }
+
+    pub fn get(&self, url: &str) -> Request {
+        self.request("GET", url)
+    }
+
+    pub fn head(&self, url: &str) -> Request {
+        self.request("HEAD", url)
+    }
+
+    pub fn post(&self, url: &str) -> Request {
+        self.request("POST", url)
+    }
+
+    pub fn put(&self, url: &str) -> Request {
+        self.request("PUT", url)
+    }
+
+    pub fn delete(&self, url: &str) -> Request {
+        self.request("DELETE", url)
+    }
+
+    /// Runs the solve deferred by [`Agent::with_flaresolverr`]. Other requests
+    /// wait for it, and a failure only means going without clearance.
+    fn solve_pending(&self) {
+        let mut pending = self.pending_solve.lock().unwrap();
+        if let (Some(url), Some(flaresolverr)) = (pending.take(), &self.flaresolverr) {
+            if has_clearance(&self.inner.read().unwrap().agent.cookie_store(), &url) {
+                log::info!("reusing saved Cloudflare clearance for {}", url);
+            } else if let Err(e) = self.solve_challenge(flaresolverr, "GET", &url, None) {
+                log::error!(
+                    "failed to solve challenge for {} with FlareSolverr, falling back to plain requests: {}",
+                    url,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Sends the request through FlareSolverr and switches every clone of
+    /// this agent to the cookies and user agent of the solution.
+    fn solve_challenge(
+        &self,
+        flaresolverr: &FlareSolverr,
+        method: &str,
+        url: &str,
+        post_data: Option<&str>,
+    ) -> Result<FlareSolverrSolution> {
+        log::info!("solving Cloudflare challenge for {} {}", method, url);
+        let solution = flaresolverr.request(method, url, post_data)?;
+
+        let mut inner = self.inner.write().unwrap();
+        let store = convert_flaresolverr_cookies_to_ureq_cookies(
+            copy_store(&inner.agent.cookie_store()),
+            solution.cookies.clone(),
+        );
+        inner.profile = BrowserProfile::from_solution(&solution);
+        inner.pinned = true;
+        inner.rebuild(store);
+
+        Ok(solution)
+    }
+
+    pub(crate) fn cookie_header(&self, url: &str) -> Option<String> {
+        cookie_header(&self.inner.read().unwrap().agent.cookie_store(), url)
+    }
+
+    fn save_cookies(&self) {
+        if let Some(jar) = &self.cookie_jar {
+            let inner = self.inner.read().unwrap();
+            // only a chosen user agent is worth keeping, the default one
+            // follows the source
+            let user_agent = if inner.pinned {
+                inner.profile.user_agent.as_str()
+            } else {
+                ""
+            };
+            let result = jar.save(&inner.agent.cookie_store(), user_agent);
+            if let Err(e) = result {
+                log::warn!("failed to save cookie jar {}: {}", jar.path().display(), e);
+            }
+        }
+    }
+}
+
+/// Adds the [`SourceError`] of a failed request, so the host can tell a
+/// missing page from a site that wants to be tried again later.
+fn with_source_error(error: anyhow::Error) -> anyhow::Error {
+    if SourceError::find(&error).is_some() {
+        return error;
+    }
+
+    let source_error = match error.downcast_ref::<ureq::Error>() {
+        Some(ureq::Error::Status(404 | 410, _)) => SourceError::NotFound,
+        Some(ureq::Error::Status(429, response)) => SourceError::RateLimited(retry_after(response)),
+        Some(ureq::Error::Status(status, _)) => SourceError::Network(*status),
+        _ => return error,
+    };
+    error.context(source_error)
+}
+
+enum Payload<'a> {
+    Empty,
+    Form(&'a [(&'a str, &'a str)]),
+    Text(&'a str),
+    Bytes(&'a [u8]),
+    Json(serde_json::Value),
+}
+
+impl Payload<'_> {
+    /// The body as text, to tell requests to the same url apart.
+    fn key(&self) -> Option<String> {
+        match self {
+            Payload::Empty => None,
+            Payload::Form(data) => Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(data.iter())
+                    .finish(),
+            ),
+            Payload::Text(data) => Some(data.to_string()),
+            Payload::Bytes(data) => Some(String::from_utf8_lossy(data).to_string()),
+            Payload::Json(data) => Some(data.to_string()),
+        }
+    }
+}
+
+/// A request that has not been sent yet, mirroring [`ureq::Request`].
+pub struct Request {
+    agent: Agent,
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    idempotent: bool,
+    /// Whether the response came from the cache, for the metrics.
+    cache_hit: bool,
+}
+
+impl Request {
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .rev()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set(mut self, header: &str, value: &str) -> Self {
+        self.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(header));
+        self.headers.push((header.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn query(mut self, param: &str, value: &str) -> Self {
+        self.query.push((param.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Allows retrying a request whose method is not idempotent, such as the
+    /// POST forms some sites use for read-only listings.
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    pub fn call(self) -> Result<Response> {
+        self.send(Payload::Empty)
+    }
+
+    pub fn send_form(self, data: &[(&str, &str)]) -> Result<Response> {
+        self.send(Payload::Form(data))
+    }
+
+    pub fn send_string(self, data: &str) -> Result<Response> {
+        self.send(Payload::Text(data))
+    }
+
+    pub fn send_bytes(self, data: &[u8]) -> Result<Response> {
+        self.send(Payload::Bytes(data))
+    }
+
+    pub fn send_json(self, data: impl serde::Serialize) -> Result<Response> {
+        self.send(Payload::Json(serde_json::to_value(data)?))
+    }
+
+    fn build(&self) -> ureq::Request {
+        let inner = self.agent.inner.read().unwrap();
+        let mut request = inner.agent.request(&self.method, &self.url);
+        for (name, value) in &inner.profile.headers {
+            if self.header(name).is_none() {
+                request = request.set(name, value);
+            }
+        }
+        drop(inner);
+        for (name, value) in &self.headers {
+            request = request.set(name, value);
+        }
+        for (param, value) in &self.query {
+            request = request.query(param, value);
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        request
+    }
+
+    /// The request url with the query parameters added by [`Request::query`].
+    fn full_url(&self) -> String {
+        if self.query.is_empty() {
+            return self.url.clone();
+        }
+
+        match Url::parse(&self.url) {
+            Ok(mut url) => {
+                url.query_pairs_mut().extend_pairs(&self.query);
+                url.to_string()
+            }
+            Err(_) => self.url.clone(),
+        }
+    }
+
+    /// Sends this request from the FlareSolverr browser, which only knows GET
+    /// and form encoded POST requests.
+    fn solve(
+        &self,
+        flaresolverr: &FlareSolverr,
+        payload: &Payload,
+    ) -> Result<FlareSolverrSolution> {
+        let post_data = if self.method.eq_ignore_ascii_case("POST") {
+            Some(match payload {
+                Payload::Empty => String::new(),
+                Payload::Form(data) => form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(data.iter())
+                    .finish(),
+                Payload::Text(data) => data.to_string(),
+                _ => bail!("FlareSolverr can only send form data"),
+            })
+        } else {
+            None
+        };
+
+        self.agent
+            .solve_challenge(
+                flaresolverr,
+                &self.method,
+                &self.full_url(),
+                post_data.as_deref(),
+            )
+            .map_err(|e| {
+                e.context(SourceError::CloudflareChallenge)
+                    .context(format!("{} is behind a Cloudflare challenge", self.url))
+            })
+    }
+
+    fn send(mut self, payload: Payload) -> Result<Response> {
+        let start = Instant::now();
+        let result = self.exchange(&payload).map_err(with_source_error);
+        self.trace(&result, start.elapsed());
+        result.map(|received| received.response)
+    }
+
+    /// Logs the outcome of the request and adds it to the source's metrics.
+    fn trace(&self, result: &Result<Received>, latency: Duration) {
+        let source = self.agent.source.as_deref().unwrap_or("-");
+        let url = self.full_url();
+        let latency_ms = latency.as_millis();
+        match result {
+            Ok(Received { response, body_len }) => {
+                // a streamed body is not read yet, only the site's word is known
+                let bytes = body_len.map(|len| len as u64).or_else(|| {
+                    response
+                        .header("Content-Length")
+                        .and_then(|length| length.parse::<u64>().ok())
+                });
+                log::debug!(
+                    target: "networking",
+                    "source={} method={} url={} final_url={} status={} latency_ms={} bytes={} cache_hit={}",
+                    source,
+                    self.method,
+                    url,
+                    response.get_url(),
+                    response.status(),
+                    latency_ms,
+                    bytes.map_or_else(|| "-".to_string(), |bytes| bytes.to_string()),
+                    self.cache_hit
+                );
+            }
+            Err(e) => {
+                let status = match e.downcast_ref::<ureq::Error>() {
+                    Some(ureq::Error::Status(status, _)) => status.to_string(),
+                    _ => "-".to_string(),
+                };
+                log::warn!(
+                    target: "networking",
+                    "source={} method={} url={} status={} latency_ms={} error={:#}",
+                    source,
+                    self.method,
+                    url,
+                    status,
+                    latency_ms,
+                    e
+                );
+            }
+        }
+
+        if let Some(metrics) = &self.agent.metrics {
+            metrics.record(latency, result.is_err(), self.cache_hit);
+        }
+    }
+
+    fn exchange(&mut self, payload: &Payload) -> Result<Received> {
+        #[cfg(any(test, feature = "fixtures"))]
+        if let Some(fixtures) = self.agent.fixtures.clone() {
+            let url = self.full_url();
+            let body = payload.key();
+            if fixtures.mode() == FixtureMode::Replay {
+                return fixtures.replay(&self.method, &url, body.as_deref());
+            }
+
+            let result = self.dispatch(payload);
+            self.agent.save_cookies();
+            return fixtures.record(&self.method, &url, body.as_deref(), result);
+        }
+
+        let result = match self.agent.cache.clone() {
+            Some(cache) => {
+                let url = self.full_url();
+                let method = self.method.clone();
+                let mut sent = false;
+                let result = cache.fetch(&method, &url, payload.key().as_deref(), |validators| {
+                    sent = true;
+                    for (header, value) in validators {
+                        self.headers.push((header.to_string(), value));
+                    }
+                    self.dispatch(payload)
+                });
+                self.cache_hit = !sent;
+                result
+            }
+            None => self.dispatch(payload),
+        };
+        self.agent.save_cookies();
+        result
+    }
+
+    fn dispatch(&self, payload: &Payload) -> Result<Received> {
+        if let Some(flaresolverr) = &self.agent.flaresolverr {
+            if flaresolverr.mode() == FlareSolverrMode::Response {
+                self.agent.rate_limiter.wait(&self.url);
+                return solution_response(&self.solve(flaresolverr, payload)?);
+            }
+        }
+
+        let mut attempt = 0;
+        let mut solution = None;
+        self.agent.solve_pending();
+        loop {
+            self.agent.rate_limiter.wait(&self.url);
+
+            let request = self.build();
+            let result = match payload {
+                Payload::Empty => request.call(),
+                Payload::Form(data) => request.send_form(data),
+                Payload::Text(data) => request.send_string(data),
+                Payload::Bytes(data) => request.send_bytes(data),
+                Payload::Json(data) => request.send_json(data.clone()),
+            };
+
+            let error = match result {
+                Ok(response) => return Ok(Received::streamed(response)),
+                Err(ureq::Error::Status(status @ (403 | 503), response)) => {
+                    let response = BufferedResponse::read(response)?;
+                    let challenged =
+                        is_challenge(status, response.header("cf-mitigated"), &response.body);
+                    let error = ureq::Error::Status(status, response.into_response()?);
+                    if !challenged {
+                        error
+                    } else if let Some(solution) = &solution {
+                        log::warn!(
+                            "{} is still challenged with fresh clearance, using the FlareSolverr response",
+                            self.url
+                        );
+                        return solution_response(solution);
+                    } else {
+                        // waiting does not get past a challenge, only solving it does
+                        match &self.agent.flaresolverr {
+                            Some(flaresolverr) => {
+                                solution = Some(self.solve(flaresolverr, payload)?);
+                                continue;
+                            }
+                            None => {
+                                return Err(anyhow::Error::from(error)
+                                    .context(SourceError::CloudflareChallenge)
+                                    .context(format!(
+                                        "{} is behind a Cloudflare challenge",
+                                        self.url
+                                    )))
+                            }
+                        }
+                    }
+                }
+                Err(error) => error,
+            };
+
+            let delay = if self.idempotent {
+                self.agent.retry_policy.delay_for(attempt, &error)
+            } else {
+                None
+            };
+
+            match delay {
+                Some(delay) => {
+                    log::warn!(
+                        "{} {} failed ({}), retrying in {:?}",
+                        self.method,
+                        self.url,
+                        error,
+                        delay
+                    );
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                None => return Err(error.into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_server::{response, serve};
+
+    fn test_agent() -> Agent {
+        Agent::new(ureq::AgentBuilder::new().build()).with_retry_policy(
+            RetryPolicy::default()
+                .base_delay(Duration::from_millis(1))
+                .max_delay(Duration::from_millis(5)),
+        )
+    }
+
+    #[test]
+    fn test_retry_until_success() {
+        let server = serve(vec![
+            response(503, &[], "unavailable"),
+            response(502, &[], "bad gateway"),
+            response(200, &[], "ok"),
+        ]);
+
+        let body = test_agent()
+            .get(&server.url)
+            .call()
+            .unwrap()
+            .into_string()
+            .unwrap();
+
+        assert_eq!(body, "ok");
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let server = serve(vec![
+            response(503, &[], ""),
+            response(503, &[], ""),
+            response(503, &[], ""),
+        ]);
+
+        let agent = test_agent().with_retry_policy(
+            RetryPolicy::default()
+                .max_retries(2)
+                .base_delay(Duration::from_millis(1)),
+        );
+        let error = agent.get(&server.url).call().unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<ureq::Error>(),
+            Some(ureq::Error::Status(503, _))
+        ));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_honours_retry_after() {
+        let server = serve(vec![
+            response(429, &[("Retry-After", "1")], ""),
+            response(200, &[], "ok"),
+        ]);
+
+        let start = std::time::Instant::now();
+        let res = test_agent().get(&server.url).call().unwrap();
+
+        assert_eq!(res.status(), 200);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_rate_limit_delays_requests() {
+        let server = serve(vec![
+            response(200, &[], ""),
+            response(200, &[], ""),
+            response(200, &[], ""),
+            response(200, &[], ""),
+        ]);
+
+        let agent = test_agent().with_rate_limit("localhost", 2, Duration::from_millis(200));
+        let url = server.url.replace("127.0.0.1", "localhost");
+
+        let start = std::time::Instant::now();
+        for _ in 0..4 {
+            agent.get(&url).call().unwrap();
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(190));
+    }
+
+    #[test]
+    fn test_post_is_not_retried() {
+        let server = serve(vec![response(503, &[], ""), response(200, &[], "ok")]);
+
+        let res = test_agent().post(&server.url).send_form(&[("a", "b")]);
+        assert!(res.is_err());
+        assert_eq!(server.requests().len(), 1);
+
+        let res = test_agent()
+            .post(&server.url)
+            .idempotent()
+            .send_form(&[("a", "b")])
+            .unwrap();
+        assert_eq!(res.status(), 200);
+
+        let request = &server.requests()[1];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/");
+        assert_eq!(
+            request.header("Content-Type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(request.body, "a=b");
+    }
+
+    #[test]
+    fn test_requests_go_through_proxy() {
+        let proxy = serve(vec![response(407, &[], ""), response(407, &[], "")]);
+        let address = proxy.url.trim_start_matches("http://");
+
+        let agent = test_agent()
+            .with_retry_policy(RetryPolicy::none())
+            .with_proxy(Some(
+                ProxyConfig::parse(&format!("http://user:secret@{}", address)).unwrap(),
+            ));
+        assert!(agent.get("http://manga.example:8080/").call().is_err());
+
+        let preferences = [Input::Text {
+            name: crate::PROXY_PREFERENCE.to_string(),
+            state: Some(format!("http://{}", address)),
+        }];
+        agent.apply_preferences(&preferences).unwrap();
+        assert!(agent.get("http://manga.example:8080/").call().is_err());
+
+        let requests = proxy.requests();
+        assert_eq!(requests[0].method, "CONNECT");
+        assert_eq!(requests[0].path, "manga.example:8080");
+        assert_eq!(
+            requests[0].header("Proxy-Authorization"),
+            Some("basic dXNlcjpzZWNyZXQ=")
+        );
+        assert_eq!(requests[1].header("Proxy-Authorization"), None);
+    }
+
+    #[test]
+    fn test_failures_carry_source_error() {
+        let server = serve(vec![
+            response(404, &[], "missing"),
+            response(429, &[("Retry-After", "120")], ""),
+            response(
+                403,
+                &[],
+                "<html><head><title>Just a moment...</title></head></html>",
+            ),
+        ]);
+        let agent = test_agent();
+
+        let error = agent.get(&server.url).call().unwrap_err();
+        assert_eq!(SourceError::find(&error), Some(&SourceError::NotFound));
+        assert!(matches!(
+            error.downcast_ref::<ureq::Error>(),
+            Some(ureq::Error::Status(404, _))
+        ));
+
+        let error = agent.get(&server.url).call().unwrap_err();
+        assert_eq!(
+            SourceError::find(&error),
+            Some(&SourceError::RateLimited(Some(Duration::from_secs(120))))
+        );
+
+        let error = agent.get(&server.url).call().unwrap_err();
+        assert_eq!(
+            SourceError::find(&error),
+            Some(&SourceError::CloudflareChallenge)
+        );
+    }
+}
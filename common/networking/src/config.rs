@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use ureq::AgentBuilder;
+
+/// Connection settings of an [`Agent`](crate::Agent).
+///
+/// The defaults keep a site that stops answering from blocking the host for
+/// more than half a minute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentConfig {
+    pub timeout_connect: Duration,
+    /// Longest wait for any single read of the response.
+    pub timeout_read: Duration,
+    /// Limit for the whole request including the body. When set, ureq uses it
+    /// in place of `timeout_read`.
+    pub timeout: Option<Duration>,
+    pub max_idle_connections_per_host: usize,
+    /// Requests made for one call, counting the first, before ureq gives up
+    /// on the redirects. `0` returns the redirect itself.
+    pub redirects: u32,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            timeout_connect: Duration::from_secs(10),
+            timeout_read: Duration::from_secs(30),
+            timeout: None,
+            max_idle_connections_per_host: 4,
+            redirects: 5,
+        }
+    }
+}
+
+impl AgentConfig {
+    pub fn timeout_connect(mut self, timeout_connect: Duration) -> Self {
+        self.timeout_connect = timeout_connect;
+        self
+    }
+
+    pub fn timeout_read(mut self, timeout_read: Duration) -> Self {
+        self.timeout_read = timeout_read;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn max_idle_connections_per_host(mut self, max_idle_connections_per_host: usize) -> Self {
+        self.max_idle_connections_per_host = max_idle_connections_per_host;
+        self
+    }
+
+    pub fn redirects(mut self, redirects: u32) -> Self {
+        self.redirects = redirects;
+        self
+    }
+
+    pub(crate) fn apply(&self, builder: AgentBuilder) -> AgentBuilder {
+        let builder = builder
+            .timeout_connect(self.timeout_connect)
+            .timeout_read(self.timeout_read)
+            .max_idle_connections_per_host(self.max_idle_connections_per_host)
+            .redirects(self.redirects);
+
+        match self.timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{net::TcpListener, thread, time::Instant};
+
+    use super::*;
+    use crate::{
+        build_ureq_agent,
+        test_server::{response, serve},
+        RetryPolicy,
+    };
+
+    #[test]
+    fn test_read_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            // accept, but never answer
+            let _conn = listener.accept();
+            thread::sleep(Duration::from_secs(5));
+        });
+
+        let agent = build_ureq_agent(None, None)
+            .with_retry_policy(RetryPolicy::none())
+            .with_config(AgentConfig::default().timeout_read(Duration::from_millis(200)));
+
+        let start = Instant::now();
+        assert!(agent.get(&url).call().is_err());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_redirect_limit() {
+        let server = serve(vec![
+            response(302, &[("Location", "/a")], ""),
+            response(302, &[("Location", "/b")], ""),
+        ]);
+
+        let agent = build_ureq_agent(None, None)
+            .with_retry_policy(RetryPolicy::none())
+            .with_config(AgentConfig::default().redirects(2));
+
+        assert!(agent.get(&server.url).call().is_err());
+        assert_eq!(server.requests().len(), 2);
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use cookie_store::{Cookie, CookieStore};
+use ureq::{serde_json, Cookie as RawCookie};
+use url::Url;
+
+/// Directory the cookie jars of all sources are kept in.
+const COOKIE_JAR_DIR: &str = "COOKIE_JAR_DIR";
+
+/// Where the cookies of `source_id` are saved, if `COOKIE_JAR_DIR` is set.
+pub fn cookie_jar_path(source_id: i64) -> Option<PathBuf> {
+    env::var_os(COOKIE_JAR_DIR).map(|dir| PathBuf::from(dir).join(format!("{}.json", source_id)))
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct CookieJarFile {
+    // Cloudflare only honours a clearance for the user agent it was issued to
+    #[serde(default)]
+    user_agent: Option<String>,
+    #[serde(default)]
+    cookies: Vec<Cookie<'static>>,
+}
+
+/// Persistent cookies of an agent, kept on disk so they survive restarts.
+///
+/// Session cookies and expired cookies are never written, and expired ones
+/// are dropped again when the jar is loaded.
+pub(crate) struct CookieJar {
+    path: PathBuf,
+    saved: Mutex<String>,
+}
+
+impl CookieJar {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            saved: Mutex::new(String::new()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the saved cookies and user agent, if the jar exists.
+    pub fn load(&self) -> Result<Option<(Vec<Cookie<'static>>, Option<String>)>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let file: CookieJarFile = serde_json::from_str(&content)?;
+        *self.saved.lock().unwrap() = content;
+
+        let cookies = file
+            .cookies
+            .into_iter()
+            .filter(|cookie| !cookie.is_expired())
+            .collect();
+
+        Ok(Some((cookies, file.user_agent)))
+    }
+
+    /// Writes the cookies to disk, unless nothing changed since the last save.
+    pub fn save(&self, store: &CookieStore, user_agent: &str) -> Result<()> {
+        let file = CookieJarFile {
+            user_agent: Some(user_agent.to_string()).filter(|ua| !ua.is_empty()),
+            cookies: store
+                .iter_unexpired()
+                .filter(|cookie| cookie.is_persistent())
+                .cloned()
+                .collect(),
+        };
+        let content = serde_json::to_string_pretty(&file)?;
+
+        let mut saved = self.saved.lock().unwrap();
+        if *saved == content {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // write to the side first so a crash never leaves half a jar behind
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, &content)?;
+        fs::rename(&tmp, &self.path)?;
+
+        *saved = content;
+
+        Ok(())
+    }
+}
+
+/// The URL a cookie of `domain` is set from, as if the site had sent it.
+fn domain_url(domain: &str) -> Option<Url> {
+    match Url::parse(&format!("https://{}", domain.trim_start_matches('.'))) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            log::warn!("ignoring cookie of bad domain {}: {}", domain, e);
+            None
+        }
+    }
+}
+
+pub(crate) fn insert_cookie(store: &mut CookieStore, cookie: Cookie<'static>) {
+    let request_url = match cookie.domain.as_cow().and_then(|domain| domain_url(&domain)) {
+        Some(url) => url,
+        None => return,
+    };
+
+    let name = cookie.name().to_string();
+    if let Err(e) = store.insert(cookie, &request_url) {
+        log::warn!("failed to insert cookie {}: {}", name, e);
+    }
+}
+
+/// Inserts a cookie that did not come in a response, such as one of a
+/// FlareSolverr solution.
+pub(crate) fn insert_raw_cookie(store: &mut CookieStore, cookie: &RawCookie) {
+    let request_url = match cookie.domain().and_then(domain_url) {
+        Some(url) => url,
+        None => return,
+    };
+
+    if let Err(e) = store.insert_raw(cookie, &request_url) {
+        log::warn!("failed to insert cookie {}: {}", cookie.name(), e);
+    }
+}
+
+/// Copies the unexpired cookies of `store` into a new store.
+pub(crate) fn copy_store(store: &CookieStore) -> CookieStore {
+    let mut copy = CookieStore::default();
+    for cookie in store.iter_unexpired() {
+        insert_cookie(&mut copy, cookie.clone());
+    }
+
+    copy
+}
+
+/// Whether `store` holds a Cloudflare clearance that is sent along to `url`.
+pub(crate) fn has_clearance(store: &CookieStore, url: &str) -> bool {
+    match Url::parse(url) {
+        Ok(url) => store
+            .get_request_values(&url)
+            .any(|(name, _)| name == "cf_clearance"),
+        Err(_) => false,
+    }
+}
+
+/// The `Cookie` header a browser would send to `url`.
+pub(crate) fn cookie_header(store: &CookieStore, url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let cookies: Vec<_> = store
+        .get_request_values(&url)
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    Some(cookies.join("; ")).filter(|header| !header.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread, time::Duration};
+
+    use super::*;
+    use crate::{
+        build_flaresolverr_client, build_ureq_agent,
+        test_server::{response, serve},
+    };
+
+    fn jar_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("networking-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("1.json")
+    }
+
+    #[test]
+    fn test_cookies_survive_restart() {
+        let path = jar_path("restart");
+        let server = serve(vec![
+            response(
+                200,
+                &[
+                    ("Set-Cookie", "login=abc; Max-Age=3600; Path=/"),
+                    ("Set-Cookie", "visit=1; Path=/"),
+                ],
+                "",
+            ),
+            response(200, &[], ""),
+        ]);
+
+        let agent = build_ureq_agent(Some("Test Browser"), None).with_cookie_jar(&path);
+        agent.get(&server.url).call().unwrap();
+        drop(agent);
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("login=abc"), "{}", saved);
+        assert!(!saved.contains("visit=1"), "{}", saved);
+
+        let agent = build_ureq_agent(None, None).with_cookie_jar(&path);
+        agent.get(&server.url).call().unwrap();
+
+        let request = &server.requests()[1];
+        assert_eq!(request.header("Cookie"), Some("login=abc"));
+        assert_eq!(request.header("User-Agent"), Some("Test Browser"));
+    }
+
+    #[test]
+    fn test_expired_cookies_are_pruned() {
+        let path = jar_path("pruned");
+        let server = serve(vec![response(
+            200,
+            &[
+                ("Set-Cookie", "short=1; Max-Age=1; Path=/"),
+                ("Set-Cookie", "long=2; Max-Age=3600; Path=/"),
+            ],
+            "",
+        )]);
+
+        let agent = build_ureq_agent(None, None).with_cookie_jar(&path);
+        agent.get(&server.url).call().unwrap();
+
+        thread::sleep(Duration::from_millis(1100));
+
+        let (cookies, user_agent) = CookieJar::new(path).load().unwrap().unwrap();
+        let names: Vec<_> = cookies.iter().map(|cookie| cookie.name()).collect();
+        assert_eq!(names, vec!["long"]);
+        assert_eq!(user_agent, None);
+    }
+
+    #[test]
+    fn test_saved_clearance_skips_solve() {
+        let path = jar_path("clearance");
+        let flaresolverr = serve(vec![response(500, &[], "")]);
+        let site = serve(vec![
+            response(
+                200,
+                &[("Set-Cookie", "cf_clearance=saved; Max-Age=3600; Path=/")],
+                "",
+            ),
+            response(200, &[], "content"),
+        ]);
+
+        let agent = build_ureq_agent(Some("Solving Browser"), None).with_cookie_jar(&path);
+        agent.get(&site.url).call().unwrap();
+        drop(agent);
+
+        let agent = build_flaresolverr_client(&site.url, &flaresolverr.url).with_cookie_jar(&path);
+        let body = agent.get(&site.url).call().unwrap().into_string().unwrap();
+        assert_eq!(body, "content");
+
+        assert!(flaresolverr.requests().is_empty());
+        let request = &site.requests()[1];
+        assert_eq!(request.header("Cookie"), Some("cf_clearance=saved"));
+        assert_eq!(request.header("User-Agent"), Some("Solving Browser"));
+    }
+}
@@ -0,0 +1,214 @@
+//! Recorded responses that let extension tests run without the network.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use ureq::serde_json;
+
+use crate::{
+    cache::fnv1a,
+    response::{BufferedResponse, Received},
+};
+
+/// Selects the [`FixtureMode`] of every agent with fixtures: `live`, `record`
+/// or `replay`.
+const NETWORKING_FIXTURES: &str = "NETWORKING_FIXTURES";
+
+/// Directory of the fixtures of `source`, in the crate the macro is used in.
+#[macro_export]
+macro_rules! fixtures_dir {
+    ($source:expr) => {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/", $source)
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Requests go to the site and nothing is written.
+    Live,
+    /// Requests go to the site and every response is saved as a fixture.
+    Record,
+    /// Requests are answered from the fixtures, without touching the network.
+    Replay,
+}
+
+impl FixtureMode {
+    /// The mode set in `NETWORKING_FIXTURES`. Without it, sources that have
+    /// fixtures in `dir` replay them and the others stay live.
+    pub fn from_env(dir: &Path) -> Result<Self> {
+        match env::var(NETWORKING_FIXTURES).ok().as_deref() {
+            Some("live") => Ok(Self::Live),
+            Some("record") => Ok(Self::Record),
+            Some("replay") => Ok(Self::Replay),
+            Some(mode) => Err(anyhow!("unknown {} mode {}", NETWORKING_FIXTURES, mode)),
+            None if dir.is_dir() => Ok(Self::Replay),
+            None => Ok(Self::Live),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct FixtureRequest {
+    method: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct Fixture {
+    request: FixtureRequest,
+    response: BufferedResponse,
+}
+
+/// Request/response pairs kept as one JSON file per request in `dir`.
+pub(crate) struct Fixtures {
+    dir: PathBuf,
+    mode: FixtureMode,
+}
+
+impl Fixtures {
+    pub fn new(dir: PathBuf, mode: FixtureMode) -> Self {
+        Self { dir, mode }
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// Answers the request with its fixture, as the site answered it.
+    pub fn replay(&self, method: &str, url: &str, body: Option<&str>) -> Result<Received> {
+        let path = self.path(method, url, body);
+        let content = fs::read_to_string(&path).map_err(|e| {
+            anyhow!(
+                "no fixture for {} {} at {} ({}), record it with {}=record",
+                method,
+                url,
+                path.display(),
+                e,
+                NETWORKING_FIXTURES
+            )
+        })?;
+        let fixture: Fixture = serde_json::from_str(&content)?;
+
+        into_result(fixture.response)
+    }
+
+    /// Saves the response the site gave, and hands it on unchanged.
+    pub fn record(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+        result: Result<Received>,
+    ) -> Result<Received> {
+        let response = match result {
+            Ok(received) => received.response,
+            Err(e) => match e.downcast::<ureq::Error>() {
+                Ok(ureq::Error::Status(_, response)) => response,
+                Ok(e) => return Err(e.into()),
+                Err(e) => return Err(e),
+            },
+        };
+
+        let fixture = Fixture {
+            request: FixtureRequest {
+                method: method.to_string(),
+                url: url.to_string(),
+                body: body.map(str::to_string),
+            },
+            response: BufferedResponse::read(response)?,
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.path(method, url, body),
+            serde_json::to_string_pretty(&fixture)?,
+        )?;
+
+        into_result(fixture.response)
+    }
+
+    /// `<method>-<url>-<hash>.json`, readable enough to find a fixture by
+    /// hand while the hash tells apart long urls and request bodies.
+    fn path(&self, method: &str, url: &str, body: Option<&str>) -> PathBuf {
+        let readable: String = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .take(80)
+            .collect();
+        let hash = fnv1a(&[method, url, body.unwrap_or_default()]);
+
+        self.dir.join(format!(
+            "{}-{}-{:016x}.json",
+            method.to_ascii_uppercase(),
+            readable,
+            hash
+        ))
+    }
+}
+
+fn into_result(response: BufferedResponse) -> Result<Received> {
+    let status = response.status;
+    let received = response.into_received()?;
+    if status >= 400 {
+        return Err(ureq::Error::Status(status, received.response).into());
+    }
+
+    Ok(received)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        build_ureq_agent,
+        test_server::{response, serve},
+        RetryPolicy,
+    };
+
+    #[test]
+    fn test_record_then_replay() {
+        let dir = env::temp_dir().join(format!("networking-fixtures-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let server = serve(vec![
+            response(200, &[("Content-Type", "text/html")], "<html>page 1</html>"),
+            response(404, &[], "missing"),
+            response(200, &[], "posted"),
+        ]);
+        let base = server.url.clone();
+
+        let agent = build_ureq_agent(None, None)
+            .with_retry_policy(RetryPolicy::none())
+            .with_fixture_mode(&dir, FixtureMode::Record);
+        agent.get(&format!("{}/manga?page=1", base)).call().unwrap();
+        agent.get(&format!("{}/gone", base)).call().unwrap_err();
+        agent.post(&base).send_form(&[("page", "2")]).unwrap();
+        drop(server);
+
+        let agent = build_ureq_agent(None, None).with_fixture_mode(&dir, FixtureMode::Replay);
+        let response = agent.get(&format!("{}/manga?page=1", base)).call().unwrap();
+        assert_eq!(response.header("Content-Type"), Some("text/html"));
+        assert_eq!(response.into_string().unwrap(), "<html>page 1</html>");
+
+        let error = agent.get(&format!("{}/gone", base)).call().unwrap_err();
+        assert!(matches!(
+            error.downcast::<ureq::Error>().unwrap(),
+            ureq::Error::Status(404, _)
+        ));
+
+        let response = agent.post(&base).send_form(&[("page", "2")]).unwrap();
+        assert_eq!(response.into_string().unwrap(), "posted");
+
+        let error = agent
+            .get(&format!("{}/manga?page=2", base))
+            .call()
+            .unwrap_err();
+        assert!(error.to_string().contains("no fixture"), "{}", error);
+    }
+}
@@ -0,0 +1,195 @@
+use std::{collections::HashMap, io::Read};
+
+use anyhow::{anyhow, bail, Result};
+use errors::SourceError;
+
+use crate::{flaresolverr::CHALLENGE_MARKERS, Agent};
+
+/// Larger bodies are not a page of a chapter.
+const MAX_IMAGE_SIZE: u64 = 32 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    WebP,
+    Gif,
+    Avif,
+}
+
+impl ImageFormat {
+    /// The format of `bytes` going by their magic bytes, whatever the site
+    /// claims in `Content-Type`.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0xff, 0xd8, 0xff, ..] => Some(Self::Jpeg),
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => Some(Self::Png),
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(Self::WebP),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(Self::Gif),
+            [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f' | b's', ..] => {
+                Some(Self::Avif)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::WebP => "image/webp",
+            Self::Gif => "image/gif",
+            Self::Avif => "image/avif",
+        }
+    }
+}
+
+/// A downloaded page, checked to really be an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub bytes: Vec<u8>,
+    pub format: ImageFormat,
+}
+
+impl Image {
+    pub fn content_type(&self) -> &'static str {
+        self.format.content_type()
+    }
+}
+
+/// Downloads the image at `url` the way the site's reader would, sending
+/// `referer` or else the referer of the agent.
+///
+/// Sites that block hotlinking tend to answer with an HTML page and status
+/// 200, so anything that is not a known image format is an error.
+pub fn fetch_image(agent: &Agent, url: &str, referer: Option<&str>) -> Result<Image> {
+    let mut request = agent.get(url).set(
+        "Accept",
+        "image/avif,image/webp,image/apng,image/*,*/*;q=0.8",
+    );
+    if let Some(referer) = referer.or_else(|| agent.referer()) {
+        request = request.set("Referer", referer);
+    }
+    let response = request.call()?;
+
+    let mut bytes = vec![];
+    response
+        .into_reader()
+        .take(MAX_IMAGE_SIZE + 1)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_IMAGE_SIZE {
+        bail!("image {} is larger than {} bytes", url, MAX_IMAGE_SIZE);
+    }
+
+    match ImageFormat::detect(&bytes) {
+        Some(format) => Ok(Image { bytes, format }),
+        None if looks_like_html(&bytes) => {
+            let page = String::from_utf8_lossy(&bytes);
+            let error = anyhow!("{} returned an HTML page instead of an image", url);
+            if CHALLENGE_MARKERS.iter().any(|marker| page.contains(marker)) {
+                Err(error.context(SourceError::CloudflareChallenge))
+            } else {
+                Err(error)
+            }
+        }
+        None => bail!("{} is not a JPEG, PNG, WebP, GIF or AVIF image", url),
+    }
+}
+
+/// Headers the host needs to download the pages of a source itself: the
+/// agent's referer, user agent and the cookies it holds for the referer,
+/// which carry any Cloudflare clearance.
+pub fn image_headers(agent: &Agent) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    headers.insert("User-Agent".to_string(), agent.profile().user_agent);
+    if let Some(referer) = agent.referer() {
+        headers.insert("Referer".to_string(), referer.to_string());
+        if let Some(cookie) = agent.cookie_header(referer) {
+            headers.insert("Cookie".to_string(), cookie);
+        }
+    }
+    headers
+}
+
+fn looks_like_html(bytes: &[u8]) -> bool {
+    let start = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_ascii_lowercase();
+    let start = start.trim_start_matches('\u{feff}').trim_start();
+    start.starts_with("<!doctype html") || start.starts_with("<html") || start.contains("<head")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{build_ureq_agent, test_server::serve_with, RetryPolicy};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    /// Answers with a raw body, which the text-only `response` helper can't hold.
+    fn image_response(content_type: &str, body: &[u8]) -> Vec<u8> {
+        let mut raw = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+            content_type,
+            body.len()
+        )
+        .into_bytes();
+        raw.extend_from_slice(body);
+        raw
+    }
+
+    #[test]
+    fn test_detect_formats() {
+        assert_eq!(
+            ImageFormat::detect(b"\xff\xd8\xff\xe0JFIF"),
+            Some(ImageFormat::Jpeg)
+        );
+        assert_eq!(ImageFormat::detect(PNG), Some(ImageFormat::Png));
+        assert_eq!(
+            ImageFormat::detect(b"RIFF\x10\0\0\0WEBPVP8 "),
+            Some(ImageFormat::WebP)
+        );
+        assert_eq!(ImageFormat::detect(b"GIF89a\x01\0"), Some(ImageFormat::Gif));
+        assert_eq!(
+            ImageFormat::detect(b"\0\0\0\x1cftypavif\0\0"),
+            Some(ImageFormat::Avif)
+        );
+        assert_eq!(ImageFormat::detect(b"<html></html>"), None);
+        assert_eq!(ImageFormat::detect(b""), None);
+    }
+
+    #[test]
+    fn test_fetch_image() {
+        let responses = [
+            image_response("image/jpeg", PNG),
+            image_response(
+                "image/jpeg",
+                b"<!DOCTYPE html><html><head><title>Just a moment...</title></head></html>",
+            ),
+        ];
+        let server = serve_with(responses.len(), move |i, _| responses[i].clone());
+        let agent = build_ureq_agent(None, None)
+            .with_retry_policy(RetryPolicy::none())
+            .with_referer("https://site.local/");
+
+        let image = fetch_image(&agent, &format!("{}/1.jpg", server.url), None).unwrap();
+        assert_eq!(image.format, ImageFormat::Png);
+        assert_eq!(image.content_type(), "image/png");
+
+        let error = fetch_image(
+            &agent,
+            &format!("{}/2.jpg", server.url),
+            Some("https://site.local/manga/1"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            SourceError::find(&error),
+            Some(&SourceError::CloudflareChallenge)
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("Referer"), Some("https://site.local/"));
+        assert_eq!(
+            requests[1].header("Referer"),
+            Some("https://site.local/manga/1")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    // Like the rate limits, counters live for the whole process so every agent
+    // a source builds adds to the same numbers.
+    static ref SOURCES: Mutex<HashMap<String, Arc<SourceMetrics>>> = Mutex::new(HashMap::new());
+}
+
+/// Counters of the requests one source made.
+#[derive(Debug, Default)]
+pub struct SourceMetrics {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    cache_hits: AtomicU64,
+    latency_micros: AtomicU64,
+}
+
+/// The counters of a source at one point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Requests made, including the ones answered from the cache.
+    pub requests: u64,
+    /// Requests that ended in an error, after retries.
+    pub errors: u64,
+    /// Requests answered from the cache without asking the site.
+    pub cache_hits: u64,
+    pub average_latency: Duration,
+}
+
+impl SourceMetrics {
+    /// The counters shared by every agent of `source`.
+    pub fn for_source(source: &str) -> Arc<Self> {
+        SOURCES
+            .lock()
+            .unwrap()
+            .entry(source.to_string())
+            .or_default()
+            .clone()
+    }
+
+    pub(crate) fn record(&self, latency: Duration, failed: bool, cache_hit: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if failed {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        if cache_hit {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        }
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        self.latency_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let requests = self.requests.load(Ordering::Relaxed);
+        let latency_micros = self.latency_micros.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            requests,
+            errors: self.errors.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            average_latency: Duration::from_micros(
+                latency_micros.checked_div(requests).unwrap_or(0),
+            ),
+        }
+    }
+}
+
+/// The counters of `source`, all zero if it made no requests yet.
+pub fn source_metrics(source: &str) -> MetricsSnapshot {
+    SourceMetrics::for_source(source).snapshot()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        build_ureq_agent,
+        test_server::{response, serve},
+        ResponseCache, RetryPolicy,
+    };
+
+    #[test]
+    fn test_metrics_count_requests() {
+        let source = format!("metrics-test-{}", std::process::id());
+        let dir = std::env::temp_dir().join(&source);
+        let _ = std::fs::remove_dir_all(&dir);
+        let server = serve(vec![
+            response(200, &[], "directory"),
+            response(404, &[], "missing"),
+        ]);
+
+        let agent = build_ureq_agent(None, None)
+            .with_retry_policy(RetryPolicy::none())
+            .with_cache(ResponseCache::new(&dir).route("/search", Duration::from_secs(60)))
+            .with_source(&source);
+        agent.get(&format!("{}/search", server.url)).call().unwrap();
+        agent.get(&format!("{}/search", server.url)).call().unwrap();
+        agent
+            .get(&format!("{}/manga/gone", server.url))
+            .call()
+            .unwrap_err();
+
+        let metrics = source_metrics(&source);
+        assert_eq!(metrics.requests, 3);
+        assert_eq!(metrics.errors, 1);
+        assert_eq!(metrics.cache_hits, 1);
+        assert!(metrics.average_latency > Duration::ZERO);
+        assert_eq!(agent.metrics(), Some(metrics));
+        assert_eq!(server.requests().len(), 2);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{cache::fnv1a, flaresolverr::FlareSolverrSolution};
+
+/// Request headers a browser sends along with its user agent. Only these are
+/// taken from a FlareSolverr solution, the others describe the response.
+const BROWSER_HEADERS: &[&str] = &[
+    "Accept",
+    "Accept-Language",
+    "sec-ch-ua",
+    "sec-ch-ua-mobile",
+    "sec-ch-ua-platform",
+    "Upgrade-Insecure-Requests",
+];
+
+const CHROME_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+const FIREFOX_ACCEPT: &str =
+    "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8";
+const SAFARI_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+/// `(user agent, accept, sec-ch-ua platform)` of the profiles sources pick
+/// from. Chromium based browsers also send client hints, the others do not.
+const PROFILES: &[(&str, &str, Option<&str>)] = &[
+    (
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+        CHROME_ACCEPT,
+        Some("\"Windows\""),
+    ),
+    (
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+        CHROME_ACCEPT,
+        Some("\"macOS\""),
+    ),
+    (
+        "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+        CHROME_ACCEPT,
+        Some("\"Linux\""),
+    ),
+    (
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:125.0) Gecko/20100101 Firefox/125.0",
+        FIREFOX_ACCEPT,
+        None,
+    ),
+    (
+        "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0",
+        FIREFOX_ACCEPT,
+        None,
+    ),
+    (
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4.1 Safari/605.1.15",
+        SAFARI_ACCEPT,
+        None,
+    ),
+];
+
+/// The user agent and the headers that go with it, so requests look like
+/// they come from one real browser rather than a bare HTTP client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserProfile {
+    pub user_agent: String,
+    /// Sent with every request, unless the request sets the header itself.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for BrowserProfile {
+    fn default() -> Self {
+        Self::builtin(0)
+    }
+}
+
+impl BrowserProfile {
+    /// The profiles shipped with the crate.
+    pub fn all() -> Vec<Self> {
+        (0..PROFILES.len()).map(Self::builtin).collect()
+    }
+
+    /// One of the shipped profiles, always the same for `source` so a site
+    /// never sees its user agent change between runs.
+    pub fn for_source(source: &str) -> Self {
+        Self::builtin(fnv1a(&[source]) as usize % PROFILES.len())
+    }
+
+    /// The headers of the browser `user_agent` belongs to, with client hints
+    /// matching its Chrome version.
+    pub fn from_user_agent(user_agent: &str) -> Self {
+        let platform = if user_agent.contains("Windows") {
+            "\"Windows\""
+        } else if user_agent.contains("Mac OS X") {
+            "\"macOS\""
+        } else {
+            "\"Linux\""
+        };
+
+        match chrome_version(user_agent) {
+            Some(version) if !user_agent.contains("Firefox/") => Self::new(
+                user_agent,
+                CHROME_ACCEPT,
+                Some((client_hints(version), platform)),
+            ),
+            _ if user_agent.contains("Firefox/") => Self::new(user_agent, FIREFOX_ACCEPT, None),
+            _ => Self::new(user_agent, SAFARI_ACCEPT, None),
+        }
+    }
+
+    /// The browser FlareSolverr solved a challenge with. Cloudflare only
+    /// honours the clearance for that browser, so its user agent is kept and
+    /// the request headers it reported replace the guessed ones.
+    pub(crate) fn from_solution(solution: &FlareSolverrSolution) -> Self {
+        let mut profile = Self::from_user_agent(&solution.userAgent);
+        if let Some(headers) = solution.headers.as_object() {
+            for (name, value) in headers {
+                let known = BROWSER_HEADERS
+                    .iter()
+                    .find(|header| header.eq_ignore_ascii_case(name));
+                if let (Some(name), Some(value)) = (known, value.as_str()) {
+                    profile.set(name, value);
+                }
+            }
+        }
+        profile
+    }
+
+    fn builtin(index: usize) -> Self {
+        let (user_agent, accept, platform) = PROFILES[index];
+        let hints = platform.map(|platform| {
+            let version = chrome_version(user_agent).expect("chromium profiles have a version");
+            (client_hints(version), platform)
+        });
+        Self::new(user_agent, accept, hints)
+    }
+
+    fn new(user_agent: &str, accept: &str, hints: Option<(String, &str)>) -> Self {
+        let mut profile = Self {
+            user_agent: user_agent.to_string(),
+            headers: vec![],
+        };
+        profile.set("Accept", accept);
+        profile.set("Accept-Language", "en-US,en;q=0.9");
+        if let Some((brands, platform)) = hints {
+            profile.set("sec-ch-ua", &brands);
+            profile.set("sec-ch-ua-mobile", "?0");
+            profile.set("sec-ch-ua-platform", platform);
+        }
+        profile.set("Upgrade-Insecure-Requests", "1");
+        profile
+    }
+
+    fn set(&mut self, header: &str, value: &str) {
+        self.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(header));
+        self.headers.push((header.to_string(), value.to_string()));
+    }
+}
+
+/// The major version in `Chrome/<major>.x.x.x`.
+fn chrome_version(user_agent: &str) -> Option<&str> {
+    let (_, version) = user_agent.split_once("Chrome/")?;
+    version.split('.').next().filter(|major| !major.is_empty())
+}
+
+fn client_hints(version: &str) -> String {
+    format!(
+        "\"Chromium\";v=\"{0}\", \"Google Chrome\";v=\"{0}\", \"Not-A.Brand\";v=\"99\"",
+        version
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        build_ureq_agent,
+        test_server::{response, serve},
+    };
+
+    #[test]
+    fn test_source_keeps_its_profile() {
+        let server = serve(vec![response(200, &[], ""), response(200, &[], "")]);
+
+        let profile = BrowserProfile::for_source("MangaSee");
+        assert_eq!(profile, BrowserProfile::for_source("MangaSee"));
+
+        let agent = build_ureq_agent(None, None).with_source("MangaSee");
+        agent.get(&server.url).call().unwrap();
+        agent
+            .get(&server.url)
+            .set("Accept", "application/json")
+            .call()
+            .unwrap();
+
+        let requests = server.requests();
+        for request in &requests {
+            assert_eq!(
+                request.header("User-Agent"),
+                Some(profile.user_agent.as_str())
+            );
+            assert_eq!(request.header("Accept-Language"), Some("en-US,en;q=0.9"));
+        }
+        assert_ne!(requests[0].header("Accept"), Some("application/json"));
+        assert_eq!(requests[1].header("Accept"), Some("application/json"));
+    }
+
+    #[test]
+    fn test_profile_from_user_agent() {
+        let profile = BrowserProfile::from_user_agent(
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+        );
+        let hints = profile
+            .headers
+            .iter()
+            .find(|(name, _)| name == "sec-ch-ua")
+            .map(|(_, value)| value.as_str());
+        assert!(hints.unwrap().contains("v=\"120\""), "{:?}", hints);
+
+        let profile = BrowserProfile::from_user_agent(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0",
+        );
+        assert!(profile
+            .headers
+            .iter()
+            .all(|(name, _)| !name.starts_with("sec-ch-ua")));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use url::Url;
+
+lazy_static! {
+    // Buckets live for the whole process, so every agent an extension builds
+    // for the same host draws from the same budget.
+    static ref BUCKETS: Mutex<HashMap<String, Arc<Mutex<TokenBucket>>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Allows bursts of up to `capacity` requests, refilled at `rate` per second.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(permits: u32, period: Duration) -> Self {
+        let capacity = f64::from(permits.max(1));
+        Self {
+            capacity,
+            rate: capacity / period.as_secs_f64().max(f64::EPSILON),
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn configure(&mut self, permits: u32, period: Duration) {
+        let bucket = Self::new(permits, period);
+        self.capacity = bucket.capacity;
+        self.rate = bucket.rate;
+        self.tokens = self.tokens.min(self.capacity);
+    }
+
+    /// Takes a token and returns how long the caller has to wait before using it.
+    ///
+    /// Tokens may go negative, which queues callers in the order they arrived.
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity) - 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+#[derive(Clone)]
+struct HostLimit {
+    host: String,
+    bucket: Arc<Mutex<TokenBucket>>,
+}
+
+/// Per-host rate limits of an [`Agent`](crate::Agent).
+///
+/// A limit for `example.com` also covers its subdomains, unless one of them
+/// has a limit of its own.
+#[derive(Clone, Default)]
+pub(crate) struct RateLimiter {
+    limits: Vec<HostLimit>,
+}
+
+impl RateLimiter {
+    pub fn add(&mut self, host: &str, permits: u32, period: Duration) {
+        let host = host.to_ascii_lowercase();
+        let bucket = BUCKETS
+            .lock()
+            .unwrap()
+            .entry(host.clone())
+            .or_insert_with(|| Arc::new(Mutex::new(TokenBucket::new(permits, period))))
+            .clone();
+        bucket.lock().unwrap().configure(permits, period);
+
+        self.limits.retain(|limit| limit.host != host);
+        self.limits.push(HostLimit { host, bucket });
+    }
+
+    /// Blocks until a request to `url` is allowed to go out.
+    pub fn wait(&self, url: &str) {
+        if self.limits.is_empty() {
+            return;
+        }
+
+        let host = match Url::parse(url) {
+            Ok(url) => match url.host_str() {
+                Some(host) => host.to_ascii_lowercase(),
+                None => return,
+            },
+            Err(_) => return,
+        };
+
+        let limit = self
+            .limits
+            .iter()
+            .filter(|limit| {
+                host == limit.host
+                    || host
+                        .strip_suffix(&limit.host)
+                        .map_or(false, |sub| sub.ends_with('.'))
+            })
+            .max_by_key(|limit| limit.host.len());
+
+        if let Some(limit) = limit {
+            let delay = limit.bucket.lock().unwrap().reserve();
+            if !delay.is_zero() {
+                log::debug!("rate limit for {} reached, waiting {:?}", limit.host, delay);
+                thread::sleep(delay);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst() {
+        let mut bucket = TokenBucket::new(3, Duration::from_secs(1));
+
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+
+        let delay = bucket.reserve();
+        assert!(delay > Duration::from_millis(300), "{:?}", delay);
+        assert!(delay <= Duration::from_millis(334), "{:?}", delay);
+
+        // the next caller queues behind the previous one
+        assert!(bucket.reserve() > Duration::from_millis(600));
+    }
+
+    #[test]
+    fn test_subdomain_uses_parent_limit() {
+        let mut limiter = RateLimiter::default();
+        limiter.add("ratelimit.test", 1, Duration::from_millis(100));
+
+        let start = Instant::now();
+        limiter.wait("https://ratelimit.test/a");
+        limiter.wait("https://api.ratelimit.test/b");
+        limiter.wait("https://ratelimit.test/c");
+        assert!(start.elapsed() >= Duration::from_millis(190));
+
+        let start = Instant::now();
+        limiter.wait("https://notratelimit.test/");
+        limiter.wait("https://notratelimit.test/");
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+}
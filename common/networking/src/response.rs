@@ -0,0 +1,90 @@
+use std::io;
+
+use ureq::Response;
+
+/// A response, with the length of its body when that was read into memory on
+/// the way instead of being left to stream.
+pub(crate) struct Received {
+    pub response: Response,
+    pub body_len: Option<usize>,
+}
+
+impl Received {
+    pub fn streamed(response: Response) -> Self {
+        Self {
+            response,
+            body_len: None,
+        }
+    }
+}
+
+/// A response read into memory, so it can be inspected and then handed out
+/// again as a regular [`Response`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct BufferedResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl BufferedResponse {
+    pub fn read(response: Response) -> io::Result<Self> {
+        let status = response.status();
+        let status_text = response.status_text().to_string();
+        let mut names = response.headers_names();
+        names.sort();
+        names.dedup();
+        let headers = names
+            .into_iter()
+            .flat_map(|name| {
+                response
+                    .all(&name)
+                    .into_iter()
+                    .map(|value| (name.clone(), value.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let body = response.into_string()?;
+
+        Ok(Self {
+            status,
+            status_text,
+            headers,
+            body,
+        })
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn into_received(self) -> anyhow::Result<Received> {
+        let body_len = self.body.len();
+        Ok(Received {
+            response: self.into_response()?,
+            body_len: Some(body_len),
+        })
+    }
+
+    pub fn into_response(self) -> anyhow::Result<Response> {
+        let mut raw = format!("HTTP/1.1 {} {}\r\n", self.status, self.status_text);
+        for (name, value) in &self.headers {
+            // the body is already decoded and in full
+            if ["content-length", "content-encoding", "transfer-encoding"]
+                .iter()
+                .any(|skip| name.eq_ignore_ascii_case(skip))
+            {
+                continue;
+            }
+            raw.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        raw.push_str(&format!("Content-Length: {}\r\n\r\n", self.body.len()));
+        raw.push_str(&self.body);
+
+        Ok(raw.parse()?)
+    }
+}
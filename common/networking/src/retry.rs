@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use ureq::{Error, ErrorKind, Response};
+
+/// Decides whether, and after how long, a failed request is sent again.
+///
+/// The delay doubles with every attempt, starting at `base_delay` and capped
+/// at `max_delay`, with a random jitter of up to half the delay so that
+/// parallel requests do not hit the site in lockstep. A `Retry-After` header
+/// on the response takes precedence over the computed delay.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Upper bound for a server supplied `Retry-After`, longer waits fail instead.
+    pub max_retry_after: Duration,
+    pub retry_statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            max_retry_after: Duration::from_secs(60),
+            retry_statuses: vec![408, 429, 500, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn max_retry_after(mut self, max_retry_after: Duration) -> Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    pub fn retry_statuses(mut self, retry_statuses: Vec<u16>) -> Self {
+        self.retry_statuses = retry_statuses;
+        self
+    }
+
+    /// Returns how long to wait before sending attempt `attempt + 1`, or `None`
+    /// if the error should be returned to the caller.
+    pub fn delay_for(&self, attempt: u32, error: &Error) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+
+        match error {
+            Error::Status(status, response) if self.retry_statuses.contains(status) => {
+                match retry_after(response) {
+                    Some(delay) if delay > self.max_retry_after => None,
+                    Some(delay) => Some(delay),
+                    None => Some(self.backoff(attempt)),
+                }
+            }
+            Error::Transport(transport)
+                if matches!(
+                    transport.kind(),
+                    ErrorKind::Io | ErrorKind::ConnectionFailed
+                ) =>
+            {
+                Some(self.backoff(attempt))
+            }
+            _ => None,
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let millis = delay.as_millis() as u64;
+        if millis == 0 {
+            return delay;
+        }
+
+        Duration::from_millis(rand::thread_rng().gen_range(millis / 2..=millis))
+    }
+}
+
+/// Parses a `Retry-After` header given either in seconds or as an HTTP date.
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.header("Retry-After")?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Methods that can be sent twice without changing anything on the server.
+pub(crate) fn is_idempotent(method: &str) -> bool {
+    matches!(
+        method.to_ascii_uppercase().as_str(),
+        "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS" | "TRACE"
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn status_error(raw: &str) -> Error {
+        let response: Response = raw.parse().unwrap();
+        Error::Status(response.status(), response)
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default()
+            .max_retries(10)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(300));
+
+        for attempt in 0..10 {
+            let delay = policy.backoff(attempt);
+            assert!(delay <= Duration::from_millis(300), "{:?}", delay);
+        }
+        assert!(policy.backoff(0) >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_retry_after_seconds() {
+        let policy = RetryPolicy::default();
+        let error = status_error("HTTP/1.1 429 Too Many Requests\r\nRetry-After: 7\r\n\r\n");
+
+        assert_eq!(policy.delay_for(0, &error), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn test_retry_after_too_long() {
+        let policy = RetryPolicy::default().max_retry_after(Duration::from_secs(5));
+        let error = status_error("HTTP/1.1 503 Service Unavailable\r\nRetry-After: 120\r\n\r\n");
+
+        assert_eq!(policy.delay_for(0, &error), None);
+    }
+
+    #[test]
+    fn test_no_retry_for_client_errors() {
+        let policy = RetryPolicy::default();
+        let error = status_error("HTTP/1.1 404 Not Found\r\n\r\n");
+
+        assert_eq!(policy.delay_for(0, &error), None);
+    }
+
+    #[test]
+    fn test_no_retry_after_max_retries() {
+        let policy = RetryPolicy::default().max_retries(2);
+        let error = status_error("HTTP/1.1 502 Bad Gateway\r\n\r\n");
+
+        assert!(policy.delay_for(1, &error).is_some());
+        assert_eq!(policy.delay_for(2, &error), None);
+    }
+}
@@ -0,0 +1,120 @@
+//! Minimal HTTP/1.1 server used by the unit tests in place of real sites.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+pub struct TestServer {
+    pub url: String,
+    pub requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl TestServer {
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Builds a raw response with `Connection: close` so ureq never reuses the socket.
+pub fn response(status: u16, headers: &[(&str, &str)], body: &str) -> String {
+    let mut raw = format!(
+        "HTTP/1.1 {} Test\r\nConnection: close\r\nContent-Length: {}\r\n",
+        status,
+        body.len()
+    );
+    for (name, value) in headers {
+        raw.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    raw.push_str("\r\n");
+    raw.push_str(body);
+    raw
+}
+
+/// Serves `responses` in order, one per connection, then stops listening.
+pub fn serve(responses: Vec<String>) -> TestServer {
+    serve_with(responses.len(), move |i, _| responses[i].clone())
+}
+
+/// Serves `count` connections, building each response from the request it answers.
+pub fn serve_with<F, R>(count: usize, handler: F) -> TestServer
+where
+    F: Fn(usize, &RecordedRequest) -> R + Send + 'static,
+    R: AsRef<[u8]>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(vec![]));
+
+    let recorded = requests.clone();
+    thread::spawn(move || {
+        for i in 0..count {
+            let (stream, _) = match listener.accept() {
+                Ok(conn) => conn,
+                Err(_) => return,
+            };
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+
+            let mut headers = vec![];
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.push((name.trim().to_string(), value.trim().to_string()));
+                }
+            }
+
+            let length = headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, v)| v.parse::<usize>().ok())
+                .unwrap_or_default();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let request = RecordedRequest {
+                method,
+                path,
+                headers,
+                body: String::from_utf8_lossy(&body).to_string(),
+            };
+            let raw = handler(i, &request);
+            recorded.lock().unwrap().push(request);
+
+            let mut stream = reader.into_inner();
+            let _ = stream.write_all(raw.as_ref());
+            let _ = stream.flush();
+        }
+    });
+
+    TestServer { url, requests }
+}
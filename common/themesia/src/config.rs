@@ -0,0 +1,158 @@
+use dateparse::Locale;
+use tanoshi_lib::prelude::SourceInfo;
+
+/// Which of the two flavours of the MangaThemesia theme a site runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    /// The older layout, with a `div.bs` for each series in listings and
+    /// `order=update` for the latest updates.
+    Stream,
+    /// The newer layout, which orders the latest updates by `order=latest`.
+    Reader,
+}
+
+impl Theme {
+    /// The `order` of the latest updates in `/manga/?order=`.
+    pub fn latest_order(&self) -> &'static str {
+        match self {
+            Self::Stream => "update",
+            Self::Reader => "latest",
+        }
+    }
+}
+
+/// The selectors of a copy of the theme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selectors {
+    /// A series in listings and search results.
+    pub manga_item: &'static str,
+    /// The link of a series in `manga_item`, with the title in `title`.
+    pub manga_link: &'static str,
+    /// The cover of a series in `manga_item`.
+    pub manga_cover: &'static str,
+    pub detail_title: &'static str,
+    pub detail_cover: &'static str,
+    pub detail_genre: &'static str,
+    pub detail_description: &'static str,
+    /// A chapter in the chapter list of a series.
+    pub chapter_item: &'static str,
+    pub chapter_name: &'static str,
+    pub chapter_date: &'static str,
+    pub chapter_url: &'static str,
+    /// The images of a chapter, when it has no `ts_reader` script.
+    pub pages: &'static str,
+}
+
+impl Selectors {
+    /// The selectors `theme` ships with.
+    pub fn of(theme: Theme) -> Self {
+        match theme {
+            Theme::Stream => Self {
+                manga_item: "div.bs",
+                manga_link: "div.bsx > a",
+                manga_cover: "div.limit img",
+                detail_title: "h1.entry-title",
+                detail_cover: "div.thumb img",
+                detail_genre: r#".mgen a[rel="tag"]"#,
+                detail_description: "div.desc p, div.entry-content p",
+                chapter_item: "div.bxcl ul li, div.cl ul li",
+                chapter_name: "span.chapternum",
+                chapter_date: "span.rightoff, time, span.chapterdate",
+                chapter_url: ".lchx > a, span.leftoff a, div.eph-num > a",
+                pages: "div#readerarea img",
+            },
+            Theme::Reader => Self {
+                manga_item: ".utao .uta .imgu, .listupd .bs .bsx, .listo .bs .bsx",
+                manga_link: "a",
+                manga_cover: "img",
+                detail_title: "h1.entry-title",
+                detail_cover: ".infomanga > div[itemprop=image] img, .thumb img",
+                detail_genre: "div.gnr a, .mgen a, .seriestugenre a",
+                detail_description: ".desc, .entry-content[itemprop=description]",
+                chapter_item: "div.bxcl li, #chapterlist li .eph-num a",
+                chapter_name: ".lch a, .chapternum",
+                chapter_date: ".chapterdate",
+                chapter_url: "a",
+                pages: "div#readerarea img",
+            },
+        }
+    }
+}
+
+impl Default for Selectors {
+    fn default() -> Self {
+        Self::of(Theme::Stream)
+    }
+}
+
+/// Everything that sets one MangaThemesia site apart from another, starting
+/// from the defaults of its flavour of the theme.
+#[derive(Debug, Clone)]
+pub struct ThemesiaConfig {
+    pub source: SourceInfo,
+    pub theme: Theme,
+    pub selectors: Selectors,
+    pub locale: Locale,
+    /// How many servers `ts_reader` lists the pages on, offered as `Server 1`
+    /// to `Server N`.
+    pub servers: usize,
+    /// Index in the `ts_reader` sources of the one pages are taken from.
+    pub selected_server: usize,
+}
+
+impl ThemesiaConfig {
+    pub fn new(source: SourceInfo) -> Self {
+        Self {
+            source,
+            theme: Theme::Stream,
+            selectors: Selectors::of(Theme::Stream),
+            locale: Locale::default(),
+            servers: 1,
+            selected_server: 0,
+        }
+    }
+
+    /// Switches to `theme`, along with its selectors. Call it before
+    /// overriding any selector.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self.selectors = Selectors::of(theme);
+        self
+    }
+
+    pub fn manga_item_selector(mut self, selector: &'static str) -> Self {
+        self.selectors.manga_item = selector;
+        self
+    }
+
+    pub fn chapter_item_selector(mut self, selector: &'static str) -> Self {
+        self.selectors.chapter_item = selector;
+        self
+    }
+
+    pub fn pages_selector(mut self, selector: &'static str) -> Self {
+        self.selectors.pages = selector;
+        self
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Offers a choice of the first `count` servers of `ts_reader`. The
+    /// first one is read unless another is selected.
+    pub fn servers(mut self, count: usize) -> Self {
+        self.servers = count;
+        self
+    }
+
+    pub fn id(&self) -> i64 {
+        self.source.id
+    }
+
+    /// The base URL, without a trailing slash.
+    pub fn url(&self) -> &str {
+        self.source.url.trim_end_matches('/')
+    }
+}
@@ -0,0 +1,393 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use anyhow::Result;
+use errors::parse_selector;
+use lazy_static::lazy_static;
+use networking::Agent;
+use scraper::Html;
+use tanoshi_lib::prelude::{Input, InputType, TriState};
+use url::form_urlencoded::byte_serialize;
+
+use crate::ThemesiaConfig;
+
+const GENRE_FILTER: &str = "Genres";
+
+/// `(name, value of status=)` of the statuses.
+const STATUSES: &[(&str, &str)] = &[
+    ("All", ""),
+    ("Ongoing", "ongoing"),
+    ("Completed", "completed"),
+    ("Hiatus", "hiatus"),
+    ("Dropped", "dropped"),
+];
+
+/// `(name, value of type=)` of the types.
+const TYPES: &[(&str, &str)] = &[
+    ("All", ""),
+    ("Manga", "manga"),
+    ("Manhwa", "manhwa"),
+    ("Manhua", "manhua"),
+    ("Comic", "comic"),
+];
+
+/// `(name, value of order=)` of the orders.
+const ORDERS: &[(&str, &str)] = &[
+    ("Default", ""),
+    ("A-Z", "title"),
+    ("Z-A", "titlereverse"),
+    ("Latest Update", "update"),
+    ("Latest Added", "latest"),
+    ("Popular", "popular"),
+];
+
+fn select(name: &str, values: &[(&str, &str)]) -> Input {
+    Input::Select {
+        name: name.to_string(),
+        values: values
+            .iter()
+            .map(|(name, _)| InputType::String(name.to_string()))
+            .collect(),
+        state: None,
+    }
+}
+
+lazy_static! {
+    pub static ref STATUS_FILTER: Input = select("Status", STATUSES);
+    pub static ref TYPE_FILTER: Input = select("Type", TYPES);
+    pub static ref ORDER_FILTER: Input = select("Order By", ORDERS);
+
+    // The genres differ from site to site and only the filter form of the
+    // listing has them, so it is read once per source.
+    static ref GENRES: Mutex<HashMap<i64, Vec<Genre>>> = Mutex::new(HashMap::new());
+}
+
+/// A genre of the filter form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Genre {
+    pub name: String,
+    /// The value of `genre[]=`.
+    pub value: String,
+}
+
+/// The genres in the filter form of the `/manga/` listing in `body`.
+pub fn parse_genres(body: &str) -> Result<Vec<Genre>> {
+    let doc = Html::parse_document(body);
+
+    let selector = parse_selector("ul.genrez li")?;
+
+    let selector_input = parse_selector(r#"input[name="genre[]"]"#)?;
+
+    let selector_label = parse_selector("label")?;
+
+    let mut genres: Vec<Genre> = vec![];
+    for el in doc.select(&selector) {
+        let value = match el.select(&selector_input).next().and_then(|el| el.value().attr("value")) {
+            Some(value) => value.to_string(),
+            None => continue,
+        };
+        let name = el
+            .select(&selector_label)
+            .flat_map(|el| el.text())
+            .collect::<String>()
+            .trim()
+            .to_string();
+
+        if !name.is_empty() && !genres.iter().any(|genre| genre.value == value) {
+            genres.push(Genre { name, value });
+        }
+    }
+
+    Ok(genres)
+}
+
+/// The genres of the site, read from its filter form the first time.
+pub fn get_genres(config: &ThemesiaConfig, client: &Agent) -> Result<Vec<Genre>> {
+    if let Some(genres) = GENRES.lock().unwrap().get(&config.id()) {
+        return Ok(genres.clone());
+    }
+
+    let body = client.get(&format!("{}/manga/", config.url()))
+        .set("Referer", config.url())
+        .call()?
+        .into_string()?;
+
+    let genres = parse_genres(&body)?;
+    if !genres.is_empty() {
+        GENRES.lock().unwrap().insert(config.id(), genres.clone());
+    }
+
+    Ok(genres)
+}
+
+fn genre_filter(genres: &[Genre]) -> Input {
+    Input::Group {
+        name: GENRE_FILTER.to_string(),
+        state: genres
+            .iter()
+            .map(|genre| Input::State {
+                name: genre.name.clone(),
+                selected: None,
+            })
+            .collect(),
+    }
+}
+
+/// The filters of the `/manga/` listing. Without the genres if the site
+/// could not be reached.
+pub fn get_filter_list(config: &ThemesiaConfig, client: &Agent) -> Vec<Input> {
+    let mut filters = vec![];
+    match get_genres(config, client) {
+        Ok(genres) if !genres.is_empty() => filters.push(genre_filter(&genres)),
+        Ok(_) => {}
+        Err(e) => log::warn!("failed to get genres of {}: {}", config.source.name, e),
+    }
+
+    filters.extend([
+        STATUS_FILTER.clone(),
+        TYPE_FILTER.clone(),
+        ORDER_FILTER.clone(),
+    ]);
+
+    filters
+}
+
+/// Whether `filters` include or exclude a genre, and so need the values of
+/// the genres of the site.
+pub fn selects_genres(filters: &[Input]) -> bool {
+    filters.iter().any(|filter| match filter {
+        Input::Group { name, state } if name == GENRE_FILTER => state.iter().any(|genre| {
+            matches!(
+                genre,
+                Input::State {
+                    selected: Some(TriState::Included | TriState::Excluded),
+                    ..
+                }
+            )
+        }),
+        _ => false,
+    })
+}
+
+/// A search of the `/manga/` listing as the filters describe it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Search {
+    pub query: String,
+    /// Values of the genres a series must have.
+    pub include_genres: Vec<String>,
+    /// Values of the genres a series must not have.
+    pub exclude_genres: Vec<String>,
+    pub status: &'static str,
+    pub kind: &'static str,
+    pub order: &'static str,
+}
+
+impl Search {
+    /// The search of `query` and `filters`, with the values of the genres
+    /// looked up in `genres`.
+    pub fn new(genres: &[Genre], query: Option<&str>, filters: &[Input]) -> Self {
+        let mut search = Self {
+            query: query.unwrap_or_default().to_string(),
+            ..Default::default()
+        };
+
+        let genre_value = |name: &str| {
+            genres
+                .iter()
+                .find(|genre| genre.name == name)
+                .map(|genre| genre.value.clone())
+        };
+
+        let selected = |state: &i64, values: &[(&str, &'static str)]| {
+            values
+                .get(*state as usize)
+                .map(|(_, value)| *value)
+                .unwrap_or_default()
+        };
+
+        for filter in filters {
+            match filter {
+                Input::Group { name, state } if name == GENRE_FILTER => {
+                    for genre in state {
+                        if let Input::State {
+                            name,
+                            selected: Some(selected),
+                        } = genre
+                        {
+                            let value = match genre_value(name) {
+                                Some(value) => value,
+                                None => continue,
+                            };
+                            match selected {
+                                TriState::Included => search.include_genres.push(value),
+                                TriState::Excluded => search.exclude_genres.push(value),
+                                TriState::Ignored => {}
+                            }
+                        }
+                    }
+                }
+                Input::Select {
+                    state: Some(state), ..
+                } if STATUS_FILTER.eq(filter) => search.status = selected(state, STATUSES),
+                Input::Select {
+                    state: Some(state), ..
+                } if TYPE_FILTER.eq(filter) => search.kind = selected(state, TYPES),
+                Input::Select {
+                    state: Some(state), ..
+                } if ORDER_FILTER.eq(filter) => search.order = selected(state, ORDERS),
+                _ => {}
+            }
+        }
+
+        search
+    }
+
+    /// The listing of every series in `order`.
+    pub fn ordered(order: &'static str) -> Self {
+        Self {
+            order,
+            ..Default::default()
+        }
+    }
+
+    /// Whether only the query is set, which the `/?s=` search is better at.
+    pub fn is_query_only(&self) -> bool {
+        *self
+            == Self {
+                query: self.query.clone(),
+                ..Default::default()
+            }
+    }
+
+    /// The `/manga/` listing with the filters in its query string. Excluded
+    /// genres are sent with a leading dash.
+    pub fn listing_url(&self, url: &str, page: i64) -> String {
+        let mut query = vec![("page", page.to_string())];
+        if !self.query.is_empty() {
+            query.push(("title", self.query.clone()));
+        }
+        for genre in &self.include_genres {
+            query.push(("genre[]", genre.clone()));
+        }
+        for genre in &self.exclude_genres {
+            query.push(("genre[]", format!("-{}", genre)));
+        }
+        query.push(("status", self.status.to_string()));
+        query.push(("type", self.kind.to_string()));
+        query.push(("order", self.order.to_string()));
+
+        let query = query
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, byte_serialize(value.as_bytes()).collect::<String>()))
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}/manga/?{}", url, query)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tanoshi_lib::prelude::{Lang, SourceInfo};
+
+    const FILTER_FORM: &str = r#"<form class="filters" action="https://themesia.local/manga/">
+<ul class="dropdown-menu c4 genrez">
+    <li><input class="genre-item" type="checkbox" id="genre-1" name="genre[]" value="action"><label for="genre-1">Action</label></li>
+    <li><input class="genre-item" type="checkbox" id="genre-2" name="genre[]" value="martial-arts"><label for="genre-2">Martial Arts</label></li>
+    <li><input class="genre-item" type="checkbox" id="genre-3" name="genre[]" value="smut"><label for="genre-3">Smut</label></li>
+</ul>
+</form>"#;
+
+    fn config() -> ThemesiaConfig {
+        ThemesiaConfig::new(SourceInfo {
+            id: 1,
+            name: "Themesia".to_string(),
+            url: "https://themesia.local".to_string(),
+            version: "0.0.0",
+            icon: "",
+            languages: Lang::Single("en".to_string()),
+            nsfw: false,
+        })
+    }
+
+    #[test]
+    fn test_parse_genres() {
+        let genres = parse_genres(FILTER_FORM).unwrap();
+
+        assert_eq!(genres.len(), 3);
+        assert_eq!(
+            genres[1],
+            Genre {
+                name: "Martial Arts".to_string(),
+                value: "martial-arts".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_search_from_filters() {
+        let config = config();
+        let genres = parse_genres(FILTER_FORM).unwrap();
+
+        let mut filters = vec![
+            genre_filter(&genres),
+            STATUS_FILTER.clone(),
+            TYPE_FILTER.clone(),
+            ORDER_FILTER.clone(),
+        ];
+        for filter in filters.iter_mut() {
+            match filter {
+                Input::Group { state, .. } => {
+                    for genre in state.iter_mut() {
+                        if let Input::State { name, selected } = genre {
+                            *selected = match name.as_str() {
+                                "Martial Arts" => Some(TriState::Included),
+                                "Smut" => Some(TriState::Excluded),
+                                _ => None,
+                            };
+                        }
+                    }
+                }
+                Input::Select { state, .. } => *state = Some(2),
+                _ => {}
+            }
+        }
+
+        assert!(selects_genres(&filters));
+        let search = Search::new(&genres, Some("solo leveling"), &filters);
+        assert!(!search.is_query_only());
+        assert_eq!(
+            search.listing_url(config.url(), 2),
+            "https://themesia.local/manga/?page=2&title=solo+leveling&genre[]=martial-arts&genre[]=-smut&status=completed&type=manhwa&order=titlereverse"
+        );
+
+        assert!(Search::new(&genres, Some("solo"), &[]).is_query_only());
+
+        // without the genres of the site the genres are left out
+        let search = Search::new(&[], Some("solo leveling"), &filters);
+        assert_eq!(
+            search.listing_url(config.url(), 2),
+            "https://themesia.local/manga/?page=2&title=solo+leveling&status=completed&type=manhwa&order=titlereverse"
+        );
+    }
+
+    #[test]
+    fn test_selects_genres() {
+        let genres = parse_genres(FILTER_FORM).unwrap();
+        let mut filters = vec![genre_filter(&genres), STATUS_FILTER.clone()];
+        assert!(!selects_genres(&filters));
+
+        if let Input::Group { state, .. } = &mut filters[0] {
+            if let Input::State { selected, .. } = &mut state[0] {
+                *selected = Some(TriState::Ignored);
+            }
+        }
+        assert!(!selects_genres(&filters));
+
+        if let Input::Group { state, .. } = &mut filters[0] {
+            if let Input::State { selected, .. } = &mut state[0] {
+                *selected = Some(TriState::Excluded);
+            }
+        }
+        assert!(selects_genres(&filters));
+    }
+}
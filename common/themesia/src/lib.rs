@@ -0,0 +1,350 @@
+mod config;
+mod filter;
+mod reader;
+mod source;
+
+pub use config::{Selectors, Theme, ThemesiaConfig};
+pub use filter::{get_filter_list, get_genres, parse_genres, selects_genres, Genre, Search};
+pub use reader::reader_pages;
+pub use source::ThemesiaSource;
+
+use anyhow::{bail, Result};
+use errors::{parse_selector, SourceError};
+use chrono::Utc;
+use chapterparse::parse_chapter_title;
+use dateparse::parse_upload_date;
+use scraper::{ElementRef, Html};
+use tanoshi_lib::prelude::{ChapterInfo, Input, MangaInfo};
+use networking::Agent;
+use url::form_urlencoded::byte_serialize;
+
+fn get_data_src(el: &ElementRef) -> Option<String> {
+    el.value()
+        .attr("data-lazy-src")
+        .or_else(|| el.value().attr("data-src"))
+        .or_else(|| el.value().attr("src"))
+        .map(|s| s.to_string())
+}
+
+fn get_body(config: &ThemesiaConfig, url: &str, client: &Agent) -> Result<String> {
+    Ok(client.get(url)
+        .set("Referer", config.url())
+        .call()?
+        .into_string()?)
+}
+
+pub fn parse_manga_list(config: &ThemesiaConfig, body: &str) -> Result<Vec<MangaInfo>> {
+    let mut manga = vec![];
+
+    let doc = Html::parse_document(body);
+
+    let selector = parse_selector(config.selectors.manga_item)?;
+
+    for el in doc.select(&selector) {
+        let selector_name = parse_selector(config.selectors.manga_link)?;
+
+        let selector_img = parse_selector(config.selectors.manga_cover)?;
+
+        let link = el
+            .select(&selector_name)
+            .next()
+            .ok_or_else(|| SourceError::field(config.selectors.manga_link, "path"))?;
+
+        let title = link
+            .value()
+            .attr("title")
+            .ok_or_else(|| SourceError::field(config.selectors.manga_link, "title"))?;
+
+        let path = link
+            .value()
+            .attr("href")
+            .ok_or_else(|| SourceError::field(config.selectors.manga_link, "path"))?;
+
+        manga.push(MangaInfo {
+            source_id: config.id(),
+            title: title.trim().to_string(),
+            author: vec![],
+            genre: vec![],
+            status: None,
+            description: None,
+            path: path.replace(config.url(), ""),
+            cover_url: el
+                .select(&selector_img)
+                .next()
+                .and_then(|el| get_data_src(&el))
+                .unwrap_or_default(),
+        })
+    }
+
+    Ok(manga)
+}
+
+pub fn get_latest_manga(config: &ThemesiaConfig, page: i64, client: &Agent) -> Result<Vec<MangaInfo>> {
+    let search = Search::ordered(config.theme.latest_order());
+
+    let body = get_body(config, &search.listing_url(config.url(), page), client)?;
+
+    parse_manga_list(config, &body)
+}
+
+pub fn get_popular_manga(config: &ThemesiaConfig, page: i64, client: &Agent) -> Result<Vec<MangaInfo>> {
+    let search = Search::ordered("popular");
+
+    let body = get_body(config, &search.listing_url(config.url(), page), client)?;
+
+    parse_manga_list(config, &body)
+}
+
+/// Searches by `query`, `filters` or both. A search by `query` alone goes
+/// through `/?s=`, which matches more than the titles of the listing.
+pub fn search_manga(
+    config: &ThemesiaConfig,
+    page: i64,
+    query: Option<&str>,
+    filters: Option<&[Input]>,
+    client: &Agent,
+) -> Result<Vec<MangaInfo>> {
+    if query.is_none() && filters.is_none() {
+        bail!("query and filters cannot be both empty")
+    }
+
+    // filters only name the genres, the listing wants their values
+    let filters = filters.unwrap_or_default();
+    let genres = if selects_genres(filters) {
+        get_genres(config, client).unwrap_or_else(|e| {
+            log::warn!(
+                "searching {} without genres, failed to get them: {}",
+                config.source.name,
+                e
+            );
+            vec![]
+        })
+    } else {
+        vec![]
+    };
+    let search = Search::new(&genres, query, filters);
+
+    let url = if search.is_query_only() {
+        format!(
+            "{}/page/{}/?s={}",
+            config.url(),
+            page,
+            byte_serialize(search.query.as_bytes()).collect::<String>()
+        )
+    } else {
+        search.listing_url(config.url(), page)
+    };
+
+    let body = get_body(config, &url, client)?;
+
+    parse_manga_list(config, &body)
+}
+
+pub fn get_manga_detail(config: &ThemesiaConfig, path: &str, client: &Agent) -> Result<MangaInfo> {
+    let body = get_body(config, &format!("{}{}", config.url(), path), client)?;
+
+    parse_manga_detail(config, path, &body)
+}
+
+pub fn parse_manga_detail(config: &ThemesiaConfig, path: &str, body: &str) -> Result<MangaInfo> {
+    let doc = Html::parse_document(body);
+
+    let selector_name = parse_selector(config.selectors.detail_title)?;
+
+    let selector_img = parse_selector(config.selectors.detail_cover)?;
+
+    let selector_genre = parse_selector(config.selectors.detail_genre)?;
+
+    let selector_desc = parse_selector(config.selectors.detail_description)?;
+
+    Ok(MangaInfo {
+        source_id: config.id(),
+        title: doc
+            .select(&selector_name)
+            .next()
+            .and_then(|item| item.last_child())
+            .and_then(|t| t.value().as_text())
+            .ok_or_else(|| SourceError::field(config.selectors.detail_title, "title"))?
+            .trim()
+            .to_string(),
+        author: vec![],
+        genre: doc
+            .select(&selector_genre)
+            .flat_map(|el| el.text())
+            .map(|s| s.to_string())
+            .collect(),
+        status: None,
+        description: Option::from(
+            doc.select(&selector_desc)
+                .flat_map(|el| el.text())
+                .collect::<Vec<&str>>()
+                .join("")
+                .trim()
+                .to_string(),
+        ),
+        path: path.to_string().replace(config.url(), ""),
+        cover_url: doc
+            .select(&selector_img)
+            .find_map(|el| get_data_src(&el))
+            .unwrap_or_default(),
+    })
+}
+
+pub fn get_chapters(config: &ThemesiaConfig, path: &str, client: &Agent) -> Result<Vec<ChapterInfo>> {
+    let body = client.get(&format!("{}{}", config.url(), path))
+        .set("Referer", config.url())
+        .set("X-Requested-With", "XMLHttpRequest")
+        .call()?
+        .into_string()?;
+
+    parse_chapters(config, &body)
+}
+
+pub fn parse_chapters(config: &ThemesiaConfig, body: &str) -> Result<Vec<ChapterInfo>> {
+    let doc = Html::parse_document(body);
+
+    let selector = parse_selector(config.selectors.chapter_item)?;
+
+    let selector_chapter_name = parse_selector(config.selectors.chapter_name)?;
+
+    let selector_chapter_time = parse_selector(config.selectors.chapter_date)?;
+
+    let selector_chapter_url = parse_selector(config.selectors.chapter_url)?;
+
+    let chapters: Vec<ChapterInfo> = doc
+        .select(&selector)
+        .map(|el| {
+            let chapter = parse_chapter_title(
+                &el.select(&selector_chapter_name)
+                    .flat_map(|el| el.text())
+                    .collect::<String>(),
+            );
+            let chapter_time = el
+                .select(&selector_chapter_time)
+                .flat_map(|el| el.text())
+                .collect::<Vec<&str>>()
+                .join("");
+
+            // `#chapterlist li .eph-num a` matches the link of the chapter
+            // itself, which `select` does not look at
+            let path = if el.value().name() == "a" {
+                el.value().attr("href").unwrap_or_default().to_string()
+            } else {
+                el.select(&selector_chapter_url)
+                    .filter_map(|el| el.value().attr("href"))
+                    .collect::<Vec<&str>>()
+                    .join("")
+            };
+
+            ChapterInfo {
+                source_id: config.id(),
+                title: chapter.clean_title(),
+                path: path.replace(config.url(), ""),
+                number: chapter.number.unwrap_or_default(),
+                scanlator: None,
+                uploaded: parse_upload_date(&chapter_time, config.locale)
+                    .unwrap_or_else(|| Utc::now().naive_utc())
+                    .timestamp(),
+            }
+        })
+        .collect();
+
+    Ok(chapters)
+}
+
+pub fn get_pages(config: &ThemesiaConfig, path: &str, client: &Agent) -> Result<Vec<String>> {
+    let body = get_body(config, &format!("{}{}", config.url(), path), client)?;
+
+    parse_pages(config, &body)
+}
+
+/// The images of a chapter, from the `ts_reader` script if there is one or
+/// else from `#readerarea`.
+pub fn parse_pages(config: &ThemesiaConfig, body: &str) -> Result<Vec<String>> {
+    if let Some(pages) = reader_pages(body, config.selected_server)? {
+        return Ok(pages);
+    }
+
+    let doc = Html::parse_document(body);
+
+    let selector = parse_selector(config.selectors.pages)?;
+
+    Ok(doc
+        .select(&selector)
+        .flat_map(|el| get_data_src(&el))
+        .map(|p| p.trim().to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tanoshi_lib::prelude::{Lang, SourceInfo};
+
+    fn config(theme: Theme) -> ThemesiaConfig {
+        ThemesiaConfig::new(SourceInfo {
+            id: 1,
+            name: "Themesia".to_string(),
+            url: "https://themesia.local".to_string(),
+            version: "0.0.0",
+            icon: "",
+            languages: Lang::Single("en".to_string()),
+            nsfw: false,
+        })
+        .theme(theme)
+    }
+
+    #[test]
+    fn test_parse_manga_list() {
+        let stream = r#"<div class="listupd"><div class="bs"><div class="bsx">
+            <a href="https://themesia.local/manga/solo-leveling/" title="Solo Leveling">
+                <div class="limit"><img data-src="https://themesia.local/solo.jpg"></div>
+            </a>
+        </div></div></div>"#;
+        let reader = r#"<div class="utao"><div class="uta"><div class="imgu">
+            <a href="https://themesia.local/manga/solo-leveling/" title="Solo Leveling">
+                <img src="https://themesia.local/solo.jpg">
+            </a>
+        </div></div></div>"#;
+
+        for (theme, body) in [(Theme::Stream, stream), (Theme::Reader, reader)] {
+            let manga = parse_manga_list(&config(theme), body).unwrap();
+            assert_eq!(manga.len(), 1, "{:?}", theme);
+            assert_eq!(manga[0].title, "Solo Leveling");
+            assert_eq!(manga[0].path, "/manga/solo-leveling/");
+            assert_eq!(manga[0].cover_url, "https://themesia.local/solo.jpg");
+        }
+    }
+
+    #[test]
+    fn test_unexpected_item_is_an_error() {
+        let body = r#"<div class="bs"><div class="bsx"><a href="https://themesia.local/manga/solo-leveling/"></a></div></div>"#;
+
+        let error = parse_manga_list(&config(Theme::Stream), body).unwrap_err();
+        assert!(SourceError::find(&error).unwrap().is_broken());
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        let stream = r#"<div class="bxcl"><ul><li>
+            <span class="leftoff"><a href="https://themesia.local/solo-leveling-chapter-2/">
+                <span class="chapternum">Chapter 2</span>
+            </a></span>
+            <span class="rightoff">January 5, 2022</span>
+        </li></ul></div>"#;
+        let reader = r##"<div id="chapterlist"><ul><li><div class="eph-num">
+            <a href="https://themesia.local/solo-leveling-chapter-2/">
+                <span class="chapternum">Chapter 2</span>
+                <span class="chapterdate">January 5, 2022</span>
+            </a>
+        </div></li></ul></div>"##;
+
+        for (theme, body) in [(Theme::Stream, stream), (Theme::Reader, reader)] {
+            let chapters = parse_chapters(&config(theme), body).unwrap();
+            assert_eq!(chapters.len(), 1, "{:?}", theme);
+            assert_eq!(chapters[0].path, "/solo-leveling-chapter-2/");
+            assert_eq!(chapters[0].number, 2.0);
+            assert_eq!(chapters[0].uploaded, 1641340800);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Pages sent through the `ts_reader` script.
+//!
+//! Many copies of the theme leave `#readerarea` empty and fill it from
+//! `ts_reader.run({...})`, whose argument lists the images of the chapter
+//! once for each server that hosts them.
+
+use anyhow::Result;
+use serde::Deserialize;
+
+const TS_READER_RUN: &str = "ts_reader.run(";
+
+#[derive(Debug, Deserialize)]
+struct TsReader {
+    #[serde(default)]
+    sources: Vec<TsSource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TsSource {
+    #[serde(default)]
+    images: Vec<String>,
+}
+
+/// The pages in the `ts_reader` script of `body`, from the `server`th source
+/// if it has them or else from the first one that does, or `None` if the
+/// chapter has no such script.
+pub fn reader_pages(body: &str, server: usize) -> Result<Option<Vec<String>>> {
+    let start = match body.find(TS_READER_RUN) {
+        Some(start) => start + TS_READER_RUN.len(),
+        None => return Ok(None),
+    };
+
+    // only the object is read, whatever follows it
+    let reader = match serde_json::Deserializer::from_str(&body[start..])
+        .into_iter::<TsReader>()
+        .next()
+    {
+        Some(reader) => reader?,
+        None => return Ok(None),
+    };
+
+    let mut sources = reader.sources;
+    let preferred = match sources.get(server) {
+        Some(source) if !source.images.is_empty() => Some(server),
+        _ => sources.iter().position(|source| !source.images.is_empty()),
+    };
+
+    Ok(Some(match preferred {
+        Some(preferred) => sources
+            .swap_remove(preferred)
+            .images
+            .into_iter()
+            .map(|page| page.trim().to_string())
+            .collect(),
+        None => vec![],
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BODY: &str = r#"<div id="readerarea"></div>
+<script>ts_reader.run({"post_id":42,"noimagehtml":"<center>No image<\/center>","prevUrl":"","nextUrl":"https:\/\/themesia.local\/solo-leveling-chapter-2\/","mode":"full","sources":[{"source":"Server 1","images":["https:\/\/cdn1.themesia.local\/01.jpg","https:\/\/cdn1.themesia.local\/02.jpg"]},{"source":"Server 2","images":["https:\/\/cdn2.themesia.local\/01.jpg","https:\/\/cdn2.themesia.local\/02.jpg"]},{"source":"Server 3","images":[]}],"lazyload":false});</script>"#;
+
+    #[test]
+    fn test_reader_pages() {
+        assert_eq!(
+            reader_pages(BODY, 0).unwrap().unwrap(),
+            vec!["https://cdn1.themesia.local/01.jpg", "https://cdn1.themesia.local/02.jpg"]
+        );
+        assert_eq!(
+            reader_pages(BODY, 1).unwrap().unwrap(),
+            vec!["https://cdn2.themesia.local/01.jpg", "https://cdn2.themesia.local/02.jpg"]
+        );
+        // a server without images or that the chapter does not have falls
+        // back to the first one
+        for server in [2, 3] {
+            assert_eq!(
+                reader_pages(BODY, server).unwrap().unwrap()[0],
+                "https://cdn1.themesia.local/01.jpg"
+            );
+        }
+    }
+
+    #[test]
+    fn test_without_reader() {
+        let body = r#"<div id="readerarea"><img src="https://themesia.local/01.jpg"></div>"#;
+        assert_eq!(reader_pages(body, 0).unwrap(), None);
+    }
+}
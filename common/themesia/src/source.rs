@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use networking::{build_ureq_agent, image_headers, proxy_input, Agent};
+use tanoshi_lib::prelude::{ChapterInfo, Extension, Input, InputType, MangaInfo, SourceInfo};
+
+use crate::ThemesiaConfig;
+
+const SERVER_INPUT: &str = "Server";
+
+/// Which of the servers of the site to read chapters from.
+fn server_input(config: &ThemesiaConfig) -> Input {
+    Input::Select {
+        name: SERVER_INPUT.to_string(),
+        values: (1..=config.servers)
+            .map(|server| InputType::String(format!("Server {}", server)))
+            .collect(),
+        state: Some(config.selected_server as i64),
+    }
+}
+
+/// A MangaThemesia site as a whole extension, described by its
+/// [`ThemesiaConfig`].
+pub struct ThemesiaSource {
+    config: ThemesiaConfig,
+    preferences: Vec<Input>,
+    client: Agent,
+}
+
+impl ThemesiaSource {
+    pub fn new(config: ThemesiaConfig) -> Self {
+        let client = build_ureq_agent(None, None)
+            .with_source(&config.source.name)
+            .with_referer(config.url());
+
+        let mut preferences = vec![proxy_input()];
+        if config.servers > 1 {
+            preferences.push(server_input(&config));
+        }
+
+        Self {
+            config,
+            preferences,
+            client,
+        }
+    }
+
+    pub fn config(&self) -> &ThemesiaConfig {
+        &self.config
+    }
+
+    pub fn client(&self) -> &Agent {
+        &self.client
+    }
+
+    /// Replaces the agent, such as with one that replays fixtures in tests.
+    pub fn with_client(mut self, client: Agent) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+impl Extension for ThemesiaSource {
+    fn set_preferences(&mut self, preferences: Vec<Input>) -> Result<()> {
+        for input in preferences {
+            for pref in self.preferences.iter_mut() {
+                if input.eq(pref) {
+                    *pref = input.clone();
+                }
+            }
+        }
+
+        self.client.apply_preferences(&self.preferences)?;
+
+        for pref in &self.preferences {
+            if let Input::Select {
+                name,
+                state: Some(state),
+                ..
+            } = pref
+            {
+                let state = *state as usize;
+                if name == SERVER_INPUT && state < self.config.servers {
+                    self.config.selected_server = state;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_preferences(&self) -> Result<Vec<Input>> {
+        Ok(self.preferences.clone())
+    }
+
+    fn get_source_info(&self) -> SourceInfo {
+        self.config.source.clone()
+    }
+
+    fn headers(&self) -> HashMap<String, String> {
+        image_headers(&self.client)
+    }
+
+    fn filter_list(&self) -> Vec<Input> {
+        crate::get_filter_list(&self.config, &self.client)
+    }
+
+    fn get_popular_manga(&self, page: i64) -> Result<Vec<MangaInfo>> {
+        crate::get_popular_manga(&self.config, page, &self.client)
+    }
+
+    fn get_latest_manga(&self, page: i64) -> Result<Vec<MangaInfo>> {
+        crate::get_latest_manga(&self.config, page, &self.client)
+    }
+
+    fn search_manga(
+        &self,
+        page: i64,
+        query: Option<String>,
+        filters: Option<Vec<Input>>,
+    ) -> Result<Vec<MangaInfo>> {
+        crate::search_manga(
+            &self.config,
+            page,
+            query.as_deref(),
+            filters.as_deref(),
+            &self.client,
+        )
+    }
+
+    fn get_manga_detail(&self, path: String) -> Result<MangaInfo> {
+        crate::get_manga_detail(&self.config, &path, &self.client)
+    }
+
+    fn get_chapters(&self, path: String) -> Result<Vec<ChapterInfo>> {
+        crate::get_chapters(&self.config, &path, &self.client)
+    }
+
+    fn get_pages(&self, path: String) -> Result<Vec<String>> {
+        crate::get_pages(&self.config, &path, &self.client)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tanoshi_lib::prelude::Lang;
+
+    #[test]
+    fn test_server_preference() {
+        let mut source = ThemesiaSource::new(
+            ThemesiaConfig::new(SourceInfo {
+                id: 1,
+                name: "Themesia".to_string(),
+                url: "https://themesia.local".to_string(),
+                version: "0.0.0",
+                icon: "",
+                languages: Lang::Single("en".to_string()),
+                nsfw: false,
+            })
+            .servers(2),
+        );
+        assert_eq!(source.config().selected_server, 0);
+        match source.get_preferences().unwrap().last() {
+            Some(Input::Select { name, values, .. }) if name == SERVER_INPUT => {
+                assert_eq!(values.len(), 2)
+            }
+            input => panic!("{:?} should be the server preference", input),
+        }
+
+        source
+            .set_preferences(vec![Input::Select {
+                name: SERVER_INPUT.to_string(),
+                values: vec![],
+                state: Some(1),
+            }])
+            .unwrap();
+        assert_eq!(source.config().selected_server, 1);
+    }
+}
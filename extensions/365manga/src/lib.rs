@@ -1,123 +1,45 @@
-use std::env;
-
-use anyhow::bail;
-use madara::{
-    get_chapters, get_latest_manga, get_manga_detail, get_pages, get_popular_manga, search_manga,
-};
-use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar, SourceInfo};
-use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent, build_flaresolverr_client};
+use madara::{MadaraConfig, MadaraSource};
+use tanoshi_lib::prelude::{Lang, PluginRegistrar, SourceInfo};
 
 tanoshi_lib::export_plugin!(register);
 
 fn register(registrar: &mut dyn PluginRegistrar) {
-    registrar.register_function(Box::new(ThreeSixtyFiveManga::default()));
-}
-
-lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    registrar.register_function(Box::new(ThreeSixtyFiveManga::new(config())));
 }
 
 const ID: i64 = 17;
 const NAME: &str = "365Manga";
 const URL: &str = "https://harimanga.com";
 
-pub struct ThreeSixtyFiveManga {
-    preferences: Vec<Input>,
-    client: Agent,
-}
-
-impl Default for ThreeSixtyFiveManga {
-    fn default() -> Self {
-        let mut instance = Self {
-            preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
-            
-        };
-
-        // If flaresolverr_url is set, build the client with it
-        if let Ok(flaresolverr_url) = env::var("FLARESOLVERR_URL") {
-            instance.client = build_flaresolverr_client(URL, &flaresolverr_url).unwrap();
-        }
-
-        instance
-    }
-}
-
-impl Extension for ThreeSixtyFiveManga {
-    fn set_preferences(
-        &mut self,
-        preferences: Vec<Input>,
-    ) -> anyhow::Result<()> {
-        for input in preferences {
-            for pref in self.preferences.iter_mut() {
-                if input.eq(pref) {
-                    *pref = input.clone();
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    fn get_preferences(&self) -> anyhow::Result<Vec<Input>> {
-        Ok(self.preferences.clone())
-    }
-
-    fn get_source_info(&self) -> SourceInfo {
-        SourceInfo {
-            id: ID,
-            name: NAME.to_string(),
-            url: URL.to_string(),
-            version: env!("CARGO_PKG_VERSION"),
-            icon: "https://i.imgur.com/q1r31vg.png",
-            languages: Lang::Single("en".to_string()),
-            nsfw: false,
-        }
-    }
-
-    fn get_popular_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_popular_manga(URL, ID, page, &self.client)
-    }
-
-    fn get_latest_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_latest_manga(URL, ID, page, &self.client)
-    }
-
-    fn search_manga(
-        &self,
-        page: i64,
-        query: Option<String>,
-        _: Option<Vec<Input>>,
-    ) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        if let Some(query) = query {
-            search_manga(URL, ID, page, &query, false, &self.client)
-        } else {
-            bail!("query can not be empty")
-        }
-    }
-
-    fn get_manga_detail(&self, path: String) -> anyhow::Result<tanoshi_lib::prelude::MangaInfo> {
-        get_manga_detail(URL, &path, ID, &self.client)
-    }
-
-    fn get_chapters(&self, path: String) -> anyhow::Result<Vec<tanoshi_lib::prelude::ChapterInfo>> {
-        get_chapters(URL, &path, ID, None, &self.client)
-    }
-
-    fn get_pages(&self, path: String) -> anyhow::Result<Vec<String>> {
-        get_pages(URL, &path, &self.client)
-    }
+pub type ThreeSixtyFiveManga = MadaraSource;
+
+pub fn config() -> MadaraConfig {
+    MadaraConfig::new(SourceInfo {
+        id: ID,
+        name: NAME.to_string(),
+        url: URL.to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        icon: "https://i.imgur.com/q1r31vg.png",
+        languages: Lang::Single("en".to_string()),
+        nsfw: false,
+    })
+    .flaresolverr(true)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use tanoshi_lib::prelude::{Extension, Input};
 
     fn create_test_instance() -> ThreeSixtyFiveManga {
         let preferences: Vec<Input> = vec![];
 
-        let mut three_sixty_five_manga: ThreeSixtyFiveManga = ThreeSixtyFiveManga::default();
+        let instance = ThreeSixtyFiveManga::new(config());
+        let client = instance
+            .client()
+            .clone()
+            .with_fixtures(networking::fixtures_dir!("365manga"));
+        let mut three_sixty_five_manga: ThreeSixtyFiveManga = instance.with_client(client);
 
         three_sixty_five_manga.set_preferences(preferences).unwrap();
 
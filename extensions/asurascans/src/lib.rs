@@ -1,113 +1,49 @@
-use anyhow::bail;
-use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar, SourceInfo};
-use wpmangastream::{
-    get_chapters, get_latest_manga, get_manga_detail, get_pages, get_popular_manga, search_manga,
-};
-use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
-use std::env;
+use tanoshi_lib::prelude::{Lang, PluginRegistrar, SourceInfo};
+use themesia::{ThemesiaConfig, ThemesiaSource};
 
 tanoshi_lib::export_plugin!(register);
 
 fn register(registrar: &mut dyn PluginRegistrar) {
-    registrar.register_function(Box::new(AsuraScans::default()));
-}
-
-lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    registrar.register_function(Box::new(AsuraScans::new(config())));
 }
 
 const ID: i64 = 25;
 const NAME: &str = "AsuraScans";
 const URL: &str = "https://asuratoon.com";
 
-pub struct AsuraScans {
-    preferences: Vec<Input>,
-    client: Agent,
-}
-
-impl Default for AsuraScans {
-    fn default() -> Self {
-        Self {
-            preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
-        }
-    }
-}
-
-impl Extension for AsuraScans {
-    fn set_preferences(
-        &mut self,
-        preferences: Vec<Input>,
-    ) -> anyhow::Result<()> {
-        for input in preferences {
-            for pref in self.preferences.iter_mut() {
-                if input.eq(pref) {
-                    *pref = input.clone();
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    fn get_preferences(&self) -> anyhow::Result<Vec<Input>> {
-        Ok(self.preferences.clone())
-    }
-    
-    fn get_source_info(&self) -> SourceInfo {
-        SourceInfo {
-            id: ID,
-            name: NAME.to_string(),
-            url: URL.to_string(),
-            version: env!("CARGO_PKG_VERSION"),
-            icon: "https://asuratoon.com/wp-content/uploads/2021/03/Group_1.png",
-            languages: Lang::Single("en".to_string()),
-            nsfw: false,
-        }
-    }
-
-    fn get_popular_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_popular_manga(URL, ID, page, &self.client)
-    }
-
-    fn get_latest_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_latest_manga(URL, ID, page, &self.client)
-    }
-
-    fn search_manga(
-        &self,
-        page: i64,
-        query: Option<String>,
-        _: Option<Vec<Input>>,
-    ) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        if let Some(query) = query {
-            search_manga(URL, ID, page, &query, &self.client)
-        } else {
-            bail!("query can not be empty")
-        }
-    }
-
-    fn get_manga_detail(&self, path: String) -> anyhow::Result<tanoshi_lib::prelude::MangaInfo> {
-        get_manga_detail(URL, &path, ID, &self.client)
-    }
-
-    fn get_chapters(&self, path: String) -> anyhow::Result<Vec<tanoshi_lib::prelude::ChapterInfo>> {
-        get_chapters(URL, &path, ID, &self.client)
-    }
-
-    fn get_pages(&self, path: String) -> anyhow::Result<Vec<String>> {
-        get_pages(URL, &path, &self.client)
-    }
+pub type AsuraScans = ThemesiaSource;
+
+pub fn config() -> ThemesiaConfig {
+    ThemesiaConfig::new(SourceInfo {
+        id: ID,
+        name: NAME.to_string(),
+        url: URL.to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        icon: "https://asuratoon.com/wp-content/uploads/2021/03/Group_1.png",
+        languages: Lang::Single("en".to_string()),
+        nsfw: false,
+    })
+    .servers(2)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use tanoshi_lib::prelude::Extension;
+
+    fn create_test_instance() -> AsuraScans {
+        let instance = AsuraScans::new(config());
+        let client = instance
+            .client()
+            .clone()
+            .with_fixtures(networking::fixtures_dir!("asurascans"));
+
+        instance.with_client(client)
+    }
 
     #[test]
     fn test_get_latest_manga() {
-        let AsuraScans = AsuraScans::default();
+        let AsuraScans = create_test_instance();
 
         let res1 = AsuraScans.get_latest_manga(1).unwrap();
         assert!(!res1.is_empty());
@@ -124,7 +60,7 @@ mod test {
 
     #[test]
     fn test_get_popular_manga() {
-        let AsuraScans = AsuraScans::default();
+        let AsuraScans = create_test_instance();
 
         let res = AsuraScans.get_popular_manga(1).unwrap();
         assert!(!res.is_empty());
@@ -132,7 +68,7 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        let AsuraScans = AsuraScans::default();
+        let AsuraScans = create_test_instance();
 
         let res = AsuraScans
             .search_manga(1, Some("Star".to_string()), None)
@@ -143,7 +79,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let AsuraScans = AsuraScans::default();
+        let AsuraScans = create_test_instance();
 
         let res = AsuraScans
             .get_manga_detail("/manga/9331846205-reincarnation-of-the-suicidal-battle-god/".to_string())
@@ -154,7 +90,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let AsuraScans = AsuraScans::default();
+        let AsuraScans = create_test_instance();
 
         let res = AsuraScans
             .get_chapters("/manga/9331846205-reincarnation-of-the-suicidal-battle-god/".to_string())
@@ -166,7 +102,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let AsuraScans = AsuraScans::default();
+        let AsuraScans = create_test_instance();
 
         let res = AsuraScans
             .get_pages("/9643503911-reincarnation-of-the-suicidal-battle-god-chapter-1/".to_string())
@@ -1,8 +1,10 @@
 use guyalib::{get_chapters, get_manga_detail, get_manga_list, get_pages};
 use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar};
 use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
+use networking::{Agent, image_headers, ResponseCache, build_ureq_agent, proxy_input};
+use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
 
 const ID: i64 = 7;
 const NAME: &str = "Guya";
@@ -15,7 +17,7 @@ fn register(registrar: &mut dyn PluginRegistrar) {
 }
 
 lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    static ref PREFERENCES: Vec<Input> = vec![proxy_input()];
 }
 
 pub struct Guya {
@@ -27,7 +29,13 @@ impl Default for Guya {
     fn default() -> Self {
         Self {
             preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),   
+            client: build_ureq_agent(None, None)
+                .with_source(NAME)
+                .with_referer(URL)
+                .with_cache(
+                    ResponseCache::for_source(ID)
+                        .route("/api/get_all_series", Duration::from_secs(15 * 60)),
+                ),
         }
     }
 }
@@ -45,6 +53,8 @@ impl Extension for Guya {
             }
         }
 
+        self.client.apply_preferences(&self.preferences)?;
+
         Ok(())
     }
 
@@ -64,6 +74,10 @@ impl Extension for Guya {
         }
     }
 
+    fn headers(&self) -> HashMap<String, String> {
+        image_headers(&self.client)
+    }
+
     fn get_popular_manga(
         &self,
         _page: i64,
@@ -110,23 +124,32 @@ impl Extension for Guya {
 mod test {
     use super::*;
 
+    fn create_test_instance() -> Guya {
+        let mut instance = Guya::default();
+        instance.client = instance
+            .client
+            .with_fixtures(networking::fixtures_dir!("guya"));
+
+        instance
+    }
+
     #[test]
     fn test_get_popular_manga() {
-        let guya = Guya::default();
+        let guya = create_test_instance();
         let res = guya.get_popular_manga(1).unwrap();
         assert!(!res.is_empty());
     }
 
     #[test]
     fn test_get_latest_manga() {
-        let guya = Guya::default();
+        let guya = create_test_instance();
         let res = guya.get_latest_manga(1).unwrap();
         assert!(!res.is_empty());
     }
 
     #[test]
     fn test_search_manga() {
-        let guya = Guya::default();
+        let guya = create_test_instance();
         let res = guya
             .search_manga(1, Some("kaguya".to_string()), None)
             .unwrap();
@@ -135,7 +158,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let guya = Guya::default();
+        let guya = create_test_instance();
         let res = guya
             .get_manga_detail("/api/series/Kaguya-Wants-To-Be-Confessed-To/".to_string())
             .unwrap();
@@ -144,7 +167,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let guya = Guya::default();
+        let guya = create_test_instance();
         let res = guya
             .get_chapters("/api/series/Kaguya-Wants-To-Be-Confessed-To".to_string())
             .unwrap();
@@ -153,7 +176,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let guya = Guya::default();
+        let guya = create_test_instance();
         let res = guya
             .get_pages("/api/series/Kaguya-Wants-To-Be-Confessed-To/1".to_string())
             .unwrap();
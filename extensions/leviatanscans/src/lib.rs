@@ -1,113 +1,65 @@
-use anyhow::bail;
-use madara::{
-    get_chapters, get_latest_manga, get_manga_detail, get_pages, get_popular_manga, search_manga,
-};
-use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar, SourceInfo};
-use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
-use std::env;
+use madara::{MadaraConfig, MadaraSource};
+use tanoshi_lib::prelude::{Lang, PluginRegistrar, SourceInfo};
 
 tanoshi_lib::export_plugin!(register);
 
 fn register(registrar: &mut dyn PluginRegistrar) {
-    registrar.register_function(Box::new(LeviatanScan::default()));
-}
-
-lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    registrar.register_function(Box::new(LeviatanScan::new(config())));
 }
 
 const ID: i64 = 14;
 const NAME: &str = "LeviatanScans";
 const URL: &str = "https://lscomic.com";
 
-pub struct LeviatanScan {
-    preferences: Vec<Input>,
-    client: Agent,
-}
-
-impl Default for LeviatanScan {
-    fn default() -> Self {
-        Self {
-            preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
-        }
-    }
+pub type LeviatanScan = MadaraSource;
+
+pub fn config() -> MadaraConfig {
+    MadaraConfig::new(SourceInfo {
+        id: ID,
+        name: NAME.to_string(),
+        url: URL.to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        icon: "https://i.imgur.com/UhrXXFv.png",
+        languages: Lang::Single("en".to_string()),
+        nsfw: false,
+    })
+    .language("en", "")
+    .language("es", "/es")
 }
 
-impl Extension for LeviatanScan {
-    fn set_preferences(
-        &mut self,
-        preferences: Vec<Input>,
-    ) -> anyhow::Result<()> {
-        for input in preferences {
-            for pref in self.preferences.iter_mut() {
-                if input.eq(pref) {
-                    *pref = input.clone();
-                }
-            }
-        }
-
-        Ok(())
-    }
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tanoshi_lib::prelude::Extension;
 
-    fn get_preferences(&self) -> anyhow::Result<Vec<Input>> {
-        Ok(self.preferences.clone())
-    }
+    fn create_test_instance() -> LeviatanScan {
+        let instance = LeviatanScan::new(config());
+        let client = instance
+            .client()
+            .clone()
+            .with_fixtures(networking::fixtures_dir!("leviatanscans"));
 
-    fn get_source_info(&self) -> SourceInfo {
-        SourceInfo {
-            id: ID,
-            name: NAME.to_string(),
-            url: URL.to_string(),
-            version: env!("CARGO_PKG_VERSION"),
-            icon: "https://i.imgur.com/UhrXXFv.png",
-            languages: Lang::Multi(vec!["en".to_string(), "es".to_string()]),
-            nsfw: false,
-        }
+        instance.with_client(client)
     }
 
-    fn get_popular_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_popular_manga(URL, ID, page,  &self.client)
-    }
-
-    fn get_latest_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_latest_manga(URL, ID, page,  &self.client)
-    }
+    #[test]
+    fn test_languages() {
+        let source = LeviatanScan::new(config());
 
-    fn search_manga(
-        &self,
-        page: i64,
-        query: Option<String>,
-        _: Option<Vec<Input>>,
-    ) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        if let Some(query) = query {
-            search_manga(URL, ID, page, &query, false,  &self.client)
-        } else {
-            bail!("query can not be empty")
+        match source.get_source_info().languages {
+            Lang::Multi(languages) => assert_eq!(languages, vec!["en", "es"]),
+            languages => panic!("{:?} should be en and es", languages),
         }
+        assert!(source
+            .get_preferences()
+            .unwrap()
+            .iter()
+            .any(|input| input.name() == "Language"));
     }
 
-    fn get_manga_detail(&self, path: String) -> anyhow::Result<tanoshi_lib::prelude::MangaInfo> {
-        get_manga_detail(URL, &path, ID,  &self.client)
-    }
-
-    fn get_chapters(&self, path: String) -> anyhow::Result<Vec<tanoshi_lib::prelude::ChapterInfo>> {
-        get_chapters(URL, &path, ID, None,  &self.client)
-    }
-
-    fn get_pages(&self, path: String) -> anyhow::Result<Vec<String>> {
-        get_pages(URL, &path,  &self.client)
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
     #[test]
     fn test_get_latest_manga() {
-        let LeviatanScan = LeviatanScan::default();
+        let LeviatanScan = create_test_instance();
 
         let res1 = LeviatanScan.get_latest_manga(1).unwrap();
         assert!(!res1.is_empty());
@@ -124,7 +76,7 @@ mod test {
 
     #[test]
     fn test_get_popular_manga() {
-        let LeviatanScan = LeviatanScan::default();
+        let LeviatanScan = create_test_instance();
 
         let res = LeviatanScan.get_popular_manga(1).unwrap();
         assert!(!res.is_empty());
@@ -132,7 +84,7 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        let LeviatanScan = LeviatanScan::default();
+        let LeviatanScan = create_test_instance();
 
         let res = LeviatanScan
             .search_manga(1, Some("the+only".to_string()), None)
@@ -143,7 +95,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let LeviatanScan = LeviatanScan::default();
+        let LeviatanScan = create_test_instance();
 
         let res = LeviatanScan
             .get_manga_detail("/manga/survival-story-of-a-sword-king-in-a-fantasy-world/".to_string())
@@ -154,7 +106,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let LeviatanScan = LeviatanScan::default();
+        let LeviatanScan = create_test_instance();
 
         let res = LeviatanScan
             .get_chapters("/manga/survival-story-of-a-sword-king-in-a-fantasy-world/".to_string())
@@ -165,7 +117,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let LeviatanScan = LeviatanScan::default();
+        let LeviatanScan = create_test_instance();
 
         let res = LeviatanScan
             .get_pages("/manga/survival-story-of-a-sword-king-in-a-fantasy-world/chapter-1/".to_string())
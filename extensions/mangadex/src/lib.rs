@@ -5,14 +5,17 @@ use crate::dto::{
     manga::{request, ListOrder, Order, Rating},
     Relationship, Results,
 };
-use anyhow::{anyhow, bail, Result};
+use anyhow::{bail, Result};
 use dto::ResultsAtHome;
+use errors::SourceError;
 use fancy_regex::Regex;
 use tanoshi_lib::prelude::*;
 use tanoshi_lib::extensions::PluginRegistrar;
 use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
+use networking::{Agent, image_headers, build_ureq_agent, proxy_input};
+use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
 
 tanoshi_lib::export_plugin!(register);
 
@@ -21,7 +24,7 @@ fn register(registrar: &mut dyn PluginRegistrar) {
 }
 
 lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    static ref PREFERENCES: Vec<Input> = vec![proxy_input()];
 }
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -39,7 +42,11 @@ impl Default for Mangadex {
     fn default() -> Self {
         Self {
             preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
+            // https://api.mangadex.org/docs/2-limitations/#general-rate-limit
+            client: build_ureq_agent(None, None)
+                .with_source(NAME)
+                .with_referer("https://mangadex.org/")
+                .with_rate_limit("mangadex.org", 5, Duration::from_secs(1)),
         }
     }
 }
@@ -220,7 +227,7 @@ impl Mangadex {
         if let dto::Data::Multiple { data, .. } = res.data {
             Ok(data.into_iter().filter_map(map_result_to_manga).collect())
         } else {
-            bail!("invalid data");
+            Err(SourceError::SiteStructureChanged("expected a list of manga".to_string()).into())
         }
     }
 }
@@ -238,6 +245,8 @@ impl Extension for Mangadex {
             }
         }
 
+        self.client.apply_preferences(&self.preferences)?;
+
         Ok(())
     }
 
@@ -301,9 +310,9 @@ impl Extension for Mangadex {
 
         let res: Results = self.client.get(&url).call()?.into_json()?;
         if let dto::Data::Single { data, .. } = res.data {
-            map_result_to_manga(data).ok_or_else(|| anyhow!("no such manga"))
+            Ok(map_result_to_manga(data).ok_or(SourceError::NotFound)?)
         } else {
-            bail!("invalid data");
+            Err(SourceError::SiteStructureChanged("expected a single manga".to_string()).into())
         }
     }
 
@@ -317,7 +326,7 @@ impl Extension for Mangadex {
         if let dto::Data::Multiple { data, .. } = res.data {
             Ok(data.into_iter().filter_map(map_result_to_chapter).collect())
         } else {
-            bail!("invalid data");
+            Err(SourceError::SiteStructureChanged("expected a list of chapters".to_string()).into())
         }
     }
 
@@ -329,8 +338,8 @@ impl Extension for Mangadex {
         Ok(map_result_to_pages(res))
     }
 
-    fn headers(&self) -> std::collections::HashMap<String, String> {
-        std::collections::HashMap::new()
+    fn headers(&self) -> HashMap<String, String> {
+        image_headers(&self.client)
     }
 
     fn filter_list(&self) -> Vec<Input> {
@@ -342,9 +351,18 @@ impl Extension for Mangadex {
 mod test {
     use super::*;
 
+    fn create_test_instance() -> Mangadex {
+        let mut instance = Mangadex::default();
+        instance.client = instance
+            .client
+            .with_fixtures(networking::fixtures_dir!("mangadex"));
+
+        instance
+    }
+
     #[test]
     fn test_get_latest_manga() {
-        let mangadex = Mangadex::default();
+        let mangadex = create_test_instance();
 
         let res = mangadex.get_latest_manga(1).unwrap();
         assert!(!res.is_empty());
@@ -352,7 +370,7 @@ mod test {
 
     #[test]
     fn test_get_popular_manga() {
-        let mangadex = Mangadex::default();
+        let mangadex = create_test_instance();
 
         let res = mangadex.get_popular_manga(1).unwrap();
         assert!(!res.is_empty());
@@ -360,7 +378,7 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        let mangadex = Mangadex::default();
+        let mangadex = create_test_instance();
 
         let res = mangadex
             .search_manga(1, Some("komi".to_string()), None)
@@ -370,7 +388,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let mangadex = Mangadex::default();
+        let mangadex = create_test_instance();
 
         let res = mangadex
             .get_manga_detail("/manga/a96676e5-8ae2-425e-b549-7f15dd34a6d8".to_string())
@@ -380,7 +398,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let mangadex = Mangadex::default();
+        let mangadex = create_test_instance();
 
         let res = mangadex
             .get_chapters("/manga/a96676e5-8ae2-425e-b549-7f15dd34a6d8".to_string())
@@ -390,7 +408,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let mangadex = Mangadex::default();
+        let mangadex = create_test_instance();
 
         let res = mangadex
             .get_pages("/chapter/03d3e4b9-db8d-4fb5-88fc-b6a087bd6410".to_string())
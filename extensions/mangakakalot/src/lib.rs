@@ -4,7 +4,8 @@ use mangakakalot_common::{
 };
 use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar, SourceInfo};
 use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
+use networking::{Agent, image_headers, build_ureq_agent, proxy_input};
+use std::collections::HashMap;
 use std::env;
 
 tanoshi_lib::export_plugin!(register);
@@ -14,7 +15,7 @@ fn register(registrar: &mut dyn PluginRegistrar) {
 }
 
 lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    static ref PREFERENCES: Vec<Input> = vec![proxy_input()];
 }
 
 const ID: i64 = 10;
@@ -30,7 +31,7 @@ impl Default for Mangakakalot {
     fn default() -> Self {
         Self {
             preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
+            client: build_ureq_agent(None, None).with_source(NAME).with_referer(URL),
         }
     }
 }
@@ -48,6 +49,8 @@ impl Extension for Mangakakalot {
             }
         }
 
+        self.client.apply_preferences(&self.preferences)?;
+
         Ok(())
     }
 
@@ -67,6 +70,10 @@ impl Extension for Mangakakalot {
         }
     }
 
+    fn headers(&self) -> HashMap<String, String> {
+        image_headers(&self.client)
+    }
+
     fn get_popular_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
         let body = self.client.get(&format!(
             "{URL}/manga_list?type=topview&category=all&state=all&page={page}",
@@ -124,9 +131,18 @@ impl Extension for Mangakakalot {
 mod test {
     use super::*;
 
+    fn create_test_instance() -> Mangakakalot {
+        let mut instance = Mangakakalot::default();
+        instance.client = instance
+            .client
+            .with_fixtures(networking::fixtures_dir!("mangakakalot"));
+
+        instance
+    }
+
     #[test]
     fn test_get_latest_manga() {
-        let source = Mangakakalot::default();
+        let source = create_test_instance();
 
         let res1 = source.get_latest_manga(1).unwrap();
         assert!(!res1.is_empty());
@@ -144,7 +160,7 @@ mod test {
 
     #[test]
     fn test_get_popular_manga() {
-        let source = Mangakakalot::default();
+        let source = create_test_instance();
 
         let res = source.get_popular_manga(1).unwrap();
         assert!(!res.is_empty());
@@ -152,7 +168,7 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        let source = Mangakakalot::default();
+        let source = create_test_instance();
 
         let res = source
             .search_manga(1, Some("one piece".to_string()), None)
@@ -162,7 +178,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let source = Mangakakalot::default();
+        let source = create_test_instance();
 
         let res = source
             .get_manga_detail("/manga-hs951953".to_string())
@@ -192,7 +208,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let source = Mangakakalot::default();
+        let source = create_test_instance();
 
         let res = source.get_chapters("/manga-hs951953".to_string()).unwrap();
         assert!(!res.is_empty());
@@ -201,7 +217,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let source = Mangakakalot::default();
+        let source = create_test_instance();
 
         let res = source
             .get_pages("/manga-hs951953/chapter-315.3".to_string())
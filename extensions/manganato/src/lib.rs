@@ -4,7 +4,8 @@ use mangakakalot_common::{
 };
 use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar, SourceInfo};
 use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
+use networking::{Agent, image_headers, build_ureq_agent, proxy_input};
+use std::collections::HashMap;
 use std::env;
 
 tanoshi_lib::export_plugin!(register);
@@ -14,7 +15,7 @@ fn register(registrar: &mut dyn PluginRegistrar) {
 }
 
 lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    static ref PREFERENCES: Vec<Input> = vec![proxy_input()];
 }
 
 const ID: i64 = 11;
@@ -30,7 +31,7 @@ impl Default for Manganato {
     fn default() -> Self {
         Self {
             preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
+            client: build_ureq_agent(None, None).with_source(NAME).with_referer(URL),
         }
     }
 }
@@ -48,6 +49,8 @@ impl Extension for Manganato {
             }
         }
 
+        self.client.apply_preferences(&self.preferences)?;
+
         Ok(())
     }
 
@@ -67,6 +70,10 @@ impl Extension for Manganato {
         }
     }
 
+    fn headers(&self) -> HashMap<String, String> {
+        image_headers(&self.client)
+    }
+
     fn get_popular_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
         let body = self.client.get(&format!("{URL}/genre-all/{page}?type=topview"))
             .call()?
@@ -120,9 +127,18 @@ impl Extension for Manganato {
 mod test {
     use super::*;
 
+    fn create_test_instance() -> Manganato {
+        let mut instance = Manganato::default();
+        instance.client = instance
+            .client
+            .with_fixtures(networking::fixtures_dir!("manganato"));
+
+        instance
+    }
+
     #[test]
     fn test_get_latest_manga() {
-        let manganato = Manganato::default();
+        let manganato = create_test_instance();
 
         let res1 = manganato.get_latest_manga(1).unwrap();
         assert!(!res1.is_empty());
@@ -140,7 +156,7 @@ mod test {
 
     #[test]
     fn test_get_popular_manga() {
-        let manganato = Manganato::default();
+        let manganato = create_test_instance();
 
         let res = manganato.get_popular_manga(1).unwrap();
         assert!(!res.is_empty());
@@ -148,7 +164,7 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        let manganato = Manganato::default();
+        let manganato = create_test_instance();
 
         let res = manganato
             .search_manga(1, Some("one piece".to_string()), None)
@@ -158,7 +174,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let manganato = Manganato::default();
+        let manganato = create_test_instance();
 
         let res = manganato
             .get_manga_detail("/manga-hs951953".to_string())
@@ -188,7 +204,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let manganato = Manganato::default();
+        let manganato = create_test_instance();
 
         let res = manganato
             .get_chapters("/manga-hs951953".to_string())
@@ -199,7 +215,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let manganato = Manganato::default();
+        let manganato = create_test_instance();
 
         let res = manganato
             .get_pages("/manga-hs951953/chapter-315.3".to_string())
@@ -2,8 +2,10 @@ use anyhow::Result;
 use tanoshi_lib::extensions::PluginRegistrar;
 use tanoshi_lib::prelude::{Extension, Input, Lang, SourceInfo};
 use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
+use networking::{Agent, image_headers, ResponseCache, build_ureq_agent, proxy_input};
+use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
 
 tanoshi_lib::export_plugin!(register);
 
@@ -12,7 +14,7 @@ fn register(registrar: &mut dyn PluginRegistrar) {
 }
 
 lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    static ref PREFERENCES: Vec<Input> = vec![proxy_input()];
 }
 
 const ID: i64 = 3;
@@ -28,7 +30,13 @@ impl Default for Mangasee {
     fn default() -> Self {
         Self {
             preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
+            client: build_ureq_agent(None, None)
+                .with_source(NAME)
+                .with_referer(URL)
+                .with_cache(
+                    ResponseCache::for_source(ID)
+                        .route("/search", Duration::from_secs(60 * 60)),
+                ),
         }
     }
 }
@@ -46,6 +54,8 @@ impl Extension for Mangasee {
             }
         }
 
+        self.client.apply_preferences(&self.preferences)?;
+
         Ok(())
     }
 
@@ -69,6 +79,10 @@ impl Extension for Mangasee {
         nepnep::get_filter_list()
     }
 
+    fn headers(&self) -> HashMap<String, String> {
+        image_headers(&self.client)
+    }
+
     fn get_popular_manga(&self, page: i64) -> Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
         nepnep::get_popular_manga(ID, URL, page, &self.client)
     }
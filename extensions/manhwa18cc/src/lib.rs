@@ -1,143 +1,57 @@
-use anyhow::{anyhow, bail};
-use madara::{get_chapters_old, get_manga_detail, parse_manga_list, search_manga_old};
-use scraper::{Html, Selector};
-use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar, SourceInfo};
-use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
-use std::env;
+use madara::{ChapterEndpoint, ListEndpoint, MadaraConfig, MadaraSource};
+use tanoshi_lib::prelude::{Lang, PluginRegistrar, SourceInfo};
 
 tanoshi_lib::export_plugin!(register);
 
 fn register(registrar: &mut dyn PluginRegistrar) {
-    registrar.register_function(Box::new(Manhwa18cc::default()));
-}
-
-lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    registrar.register_function(Box::new(Manhwa18cc::new(config())));
 }
 
 const ID: i64 = 8;
 const NAME: &str = "Manhwa18cc";
 const URL: &str = "https://manhwa18.cc";
 
-pub struct Manhwa18cc {
-    preferences: Vec<Input>,
-    client: Agent,
-}
-
-impl Default for Manhwa18cc {
-    fn default() -> Self {
-        Self {
-            preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
-        }
-    }
-}   
-
-impl Extension for Manhwa18cc {
-    fn set_preferences(
-        &mut self,
-        preferences: Vec<Input>,
-    ) -> anyhow::Result<()> {
-        for input in preferences {
-            for pref in self.preferences.iter_mut() {
-                if input.eq(pref) {
-                    *pref = input.clone();
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    fn get_preferences(&self) -> anyhow::Result<Vec<Input>> {
-        Ok(self.preferences.clone())
-    }
-
-    fn get_source_info(&self) -> SourceInfo {
-        SourceInfo {
-            id: ID,
-            name: NAME.to_string(),
-            url: URL.to_string(),
-            version: env!("CARGO_PKG_VERSION"),
-            icon: "https://manhwa18.cc/images/favicon-160x160.png",
-            languages: Lang::Multi(vec!["en".to_string(), "ko".to_string()]),
-            nsfw: true,
-        }
-    }
-
-    fn get_popular_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        let body = self.client.get(&format!("{}/webtoons/{}?orderby=latest", URL, page)) 
-            .call()?
-            .into_string()?;
-
-        let selector = Selector::parse(".manga-item")
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
-
-        parse_manga_list(URL, ID, &body, &selector, false)
-    }
-
-    fn get_latest_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        let body = self.client.get(&format!("{}/webtoons/{}?orderby=latest", URL, page))
-            .call()?
-            .into_string()?;
-
-        let selector = Selector::parse(".manga-item")
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
-
-        parse_manga_list(URL, ID, &body, &selector, false)
-    }
-
-    fn search_manga(
-        &self,
-        page: i64,
-        query: Option<String>,
-        _: Option<Vec<Input>>,
-    ) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        if let Some(query) = query {
-            search_manga_old(URL, ID, page, &query,  &self.client)
-        } else {
-            bail!("query can not be empty")
-        }
-    }
-
-    fn get_manga_detail(&self, path: String) -> anyhow::Result<tanoshi_lib::prelude::MangaInfo> {
-        get_manga_detail(URL, &path, ID,  &self.client)
-    }
-
-    fn get_chapters(&self, path: String) -> anyhow::Result<Vec<tanoshi_lib::prelude::ChapterInfo>> {
-        get_chapters_old(URL, &path, ID,  &self.client)
-    }
-
-    fn get_pages(&self, path: String) -> anyhow::Result<Vec<String>> {
-        let body = self.client.get(&format!("{}{}", URL, path)) 
-            .call()?
-            .into_string()?;
-
-        let doc = Html::parse_document(&body);
-
-        let selector = Selector::parse(r#".read-content img"#)
-            .map_err(|e| anyhow!("failed to parse selector: {:?}", e))?;
-
-        Ok(doc
-            .select(&selector)
-            .flat_map(|el| {
-                el.value()
-                    .attr("data-src")
-                    .or_else(|| el.value().attr("src"))
-            })
-            .map(|p| p.to_string())
-            .collect())
-    }
+pub type Manhwa18cc = MadaraSource;
+
+pub fn config() -> MadaraConfig {
+    MadaraConfig::new(SourceInfo {
+        id: ID,
+        name: NAME.to_string(),
+        url: URL.to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        icon: "https://manhwa18.cc/images/favicon-160x160.png",
+        languages: Lang::Multi(vec!["en".to_string(), "ko".to_string()]),
+        nsfw: true,
+    })
+    .list_endpoint(ListEndpoint::Paged {
+        popular: "/webtoons/{page}?orderby=latest",
+        latest: "/webtoons/{page}?orderby=latest",
+        search: "/search?q={query}&page={page}",
+    })
+    .chapter_endpoint(ChapterEndpoint::Old)
+    .manga_item_selector(".manga-item")
+    .search_item_selector(".manga-item")
+    .pages_selector(".read-content img")
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use tanoshi_lib::prelude::Extension;
+
+    fn create_test_instance() -> Manhwa18cc {
+        let instance = Manhwa18cc::new(config());
+        let client = instance
+            .client()
+            .clone()
+            .with_fixtures(networking::fixtures_dir!("manhwa18cc"));
+
+        instance.with_client(client)
+    }
 
     #[test]
     fn test_get_latest_manga() {
-        let manhwa18cc = Manhwa18cc::default();
+        let manhwa18cc = create_test_instance();
 
         let res1 = manhwa18cc.get_latest_manga(1).unwrap();
         assert!(!res1.is_empty());
@@ -154,7 +68,7 @@ mod test {
 
     #[test]
     fn test_get_popular_manga() {
-        let manhwa18cc = Manhwa18cc::default();
+        let manhwa18cc = create_test_instance();
 
         let res = manhwa18cc.get_popular_manga(1).unwrap();
         assert!(!res.is_empty());
@@ -162,7 +76,7 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        let manhwa18cc = Manhwa18cc::default();
+        let manhwa18cc = create_test_instance();
 
         let res = manhwa18cc
             .search_manga(1, Some("tutoring".to_string()), None)
@@ -172,7 +86,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let manhwa18cc = Manhwa18cc::default();
+        let manhwa18cc = create_test_instance();
 
         let res = manhwa18cc
             .get_manga_detail("/webtoon/private-tutoring-in-these-trying-times".to_string())
@@ -182,7 +96,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let manhwa18cc = Manhwa18cc::default();
+        let manhwa18cc = create_test_instance();
 
         let res = manhwa18cc
             .get_chapters("/webtoon/private-tutoring-in-these-trying-times".to_string())
@@ -193,7 +107,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let manhwa18cc = Manhwa18cc::default();
+        let manhwa18cc = create_test_instance();
 
         let res = manhwa18cc
             .get_pages("//webtoon/private-tutoring-in-these-trying-times/chapter-27".to_string())
@@ -1,113 +1,50 @@
-use anyhow::bail;
-use madara::{
-    get_chapters, get_latest_manga, get_manga_detail, get_pages, get_popular_manga, search_manga,
-};
-use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar, SourceInfo};
-use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
-use std::env;
+use madara::{MadaraConfig, MadaraSource};
+use tanoshi_lib::prelude::{Lang, PluginRegistrar, SourceInfo};
 
 tanoshi_lib::export_plugin!(register);
 
 fn register(registrar: &mut dyn PluginRegistrar) {
-    registrar.register_function(Box::new(MMScans::default()));
-}
-
-lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    registrar.register_function(Box::new(MMScans::new(config())));
 }
 
 const ID: i64 = 19;
 const NAME: &str = "MMScans";
 const URL: &str = "https://mm-scans.org";
 
-pub struct MMScans {
-    preferences: Vec<Input>,
-    client: Agent,
-}
-
-impl Default for MMScans {
-    fn default() -> Self {
-        Self {
-            preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
-        }
-    }
-}
-
-impl Extension for MMScans {
-    fn set_preferences(
-        &mut self,
-        preferences: Vec<Input>,
-    ) -> anyhow::Result<()> {
-        for input in preferences {
-            for pref in self.preferences.iter_mut() {
-                if input.eq(pref) {
-                    *pref = input.clone();
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    fn get_preferences(&self) -> anyhow::Result<Vec<Input>> {
-        Ok(self.preferences.clone())
-    }
-
-    fn get_source_info(&self) -> SourceInfo {
-        SourceInfo {
-            id: ID,
-            name: NAME.to_string(),
-            url: URL.to_string(),
-            version: env!("CARGO_PKG_VERSION"),
-            icon: "https://i.imgur.com/5R7QX58.png",
-            languages: Lang::Single("en".to_string()),
-            nsfw: false,
-        }
-    }
-
-    fn get_popular_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_popular_manga(URL, ID, page, &self.client)
-    }
-
-    fn get_latest_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_latest_manga(URL, ID, page, &self.client)
-    }
-
-    fn search_manga(
-        &self,
-        page: i64,
-        query: Option<String>,
-        _: Option<Vec<Input>>,
-    ) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        if let Some(query) = query {
-            search_manga(URL, ID, page, &query, true, &self.client)
-        } else {
-            bail!("query can not be empty")
-        }
-    }
-
-    fn get_manga_detail(&self, path: String) -> anyhow::Result<tanoshi_lib::prelude::MangaInfo> {
-        get_manga_detail(URL, &path, ID, &self.client)
-    }
-
-    fn get_chapters(&self, path: String) -> anyhow::Result<Vec<tanoshi_lib::prelude::ChapterInfo>> {
-        get_chapters(URL, &path, ID, Option::from(".chapter-title-date p"), &self.client)
-    }
-
-    fn get_pages(&self, path: String) -> anyhow::Result<Vec<String>> {
-        get_pages(URL, &path, &self.client)
-    }
+pub type MMScans = MadaraSource;
+
+pub fn config() -> MadaraConfig {
+    MadaraConfig::new(SourceInfo {
+        id: ID,
+        name: NAME.to_string(),
+        url: URL.to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        icon: "https://i.imgur.com/5R7QX58.png",
+        languages: Lang::Single("en".to_string()),
+        nsfw: false,
+    })
+    .search_item_selector("a")
+    .chapter_name_selector(".chapter-title-date p")
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use tanoshi_lib::prelude::Extension;
+
+    fn create_test_instance() -> MMScans {
+        let instance = MMScans::new(config());
+        let client = instance
+            .client()
+            .clone()
+            .with_fixtures(networking::fixtures_dir!("mmscans"));
+
+        instance.with_client(client)
+    }
 
     #[test]
     fn test_get_latest_manga() {
-        let MMScans = MMScans::default();
+        let MMScans = create_test_instance();
 
         let res1 = MMScans.get_latest_manga(1).unwrap();
         assert!(!res1.is_empty());
@@ -124,7 +61,7 @@ mod test {
 
     #[test]
     fn test_get_popular_manga() {
-        let MMScans = MMScans::default();
+        let MMScans = create_test_instance();
 
         let res = MMScans.get_popular_manga(1).unwrap();
 
@@ -133,7 +70,7 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        let MMScans = MMScans::default();
+        let MMScans = create_test_instance();
 
         let res = MMScans
             .search_manga(1, Some("study".to_string()), None)
@@ -144,7 +81,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let MMScans = MMScans::default();
+        let MMScans = create_test_instance();
 
         let res = MMScans
             .get_manga_detail("/manga/ygre-t1234/".to_string())
@@ -155,7 +92,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let MMScans = MMScans::default();
+        let MMScans = create_test_instance();
 
         let res = MMScans.get_chapters("/manga/ygre-t1234/".to_string()).unwrap();
 
@@ -165,7 +102,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let MMScans = MMScans::default();
+        let MMScans = create_test_instance();
 
         let res = MMScans.get_pages("/manga/ygre-t1234/1/".to_string()).unwrap();
 
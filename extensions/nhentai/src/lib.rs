@@ -1,13 +1,16 @@
 use anyhow::{anyhow, Result};
 use chrono::NaiveDateTime;
+use errors::{parse_selector, SourceError};
 use fancy_regex::Regex;
-use scraper::{Html, Selector};
+use scraper::Html;
 use tanoshi_lib::prelude::{
     ChapterInfo, Extension, Input, InputType, Lang, MangaInfo, PluginRegistrar,
 };
 use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent, build_flaresolverr_client};
+use networking::{Agent, image_headers, build_ureq_agent, build_flaresolverr_client, proxy_input};
+use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
 
 pub static ID: i64 = 6;
 pub static NAME: &str = "nhentai";
@@ -78,7 +81,7 @@ lazy_static! {
         state: None
     };
     
-    static ref PREFERENCES: Vec<Input> = vec![LANGUAGE_SELECT.clone(), BLACKLIST_TAG.clone()];
+    static ref PREFERENCES: Vec<Input> = vec![LANGUAGE_SELECT.clone(), BLACKLIST_TAG.clone(), proxy_input()];
 }
 
 pub struct NHentai {
@@ -95,9 +98,16 @@ impl Default for NHentai {
 
         // If flaresolverr_url is set, build the client with it
         if let Ok(flaresolverr_url) = env::var("FLARESOLVERR_URL") {
-            instance.client = build_flaresolverr_client(URL, &flaresolverr_url).unwrap();
+            instance.client = build_flaresolverr_client(URL, &flaresolverr_url);
         }
 
+        instance.client = instance
+            .client
+            .with_source(NAME)
+            .with_referer(URL)
+            .with_rate_limit("nhentai.net", 1, Duration::from_secs(1))
+            .with_cookie_jar_for(ID);
+
         instance
     }
 }
@@ -190,14 +200,10 @@ impl NHentai {
             .into_string()?;
         
         let document = Html::parse_document(&res);
-        let gallery_selector =
-            Selector::parse(".gallery").map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let thumbnail_selector =
-            Selector::parse("a > img").map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let path_selector =
-            Selector::parse("a").map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let title_selector = Selector::parse("a > .caption")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
+        let gallery_selector = parse_selector(".gallery")?;
+        let thumbnail_selector = parse_selector("a > img")?;
+        let path_selector = parse_selector("a")?;
+        let title_selector = parse_selector("a > .caption")?;
 
         let mut manga_list = vec![];
         for gallery in document.select(&gallery_selector) {
@@ -206,21 +212,21 @@ impl NHentai {
                 .flat_map(|thumbnail| thumbnail.value().attr("data-src"))
                 .next()
                 .map(|s| s.to_string())
-                .ok_or_else(|| anyhow!("cover_url not found"))?;
+                .ok_or_else(|| SourceError::field("a > img", "cover_url"))?;
 
             let path = gallery
                 .select(&path_selector)
                 .flat_map(|link| link.value().attr("href"))
                 .next()
                 .map(|s| s.to_string())
-                .ok_or_else(|| anyhow!("path not found"))?;
+                .ok_or_else(|| SourceError::field("a", "path"))?;
 
             let title = gallery
                 .select(&title_selector)
                 .flat_map(|caption| caption.text().next())
                 .next()
                 .map(|s| s.to_string())
-                .ok_or_else(|| anyhow!("title not found"))?;
+                .ok_or_else(|| SourceError::field("a > .caption", "title"))?;
 
             let manga = MangaInfo {
                 source_id: ID,
@@ -252,6 +258,8 @@ impl Extension for NHentai {
             }
         }
 
+        self.client.apply_preferences(&self.preferences)?;
+
         Ok(())
     }
 
@@ -307,26 +315,16 @@ impl Extension for NHentai {
             .into_string()?;
 
         let document = Html::parse_document(&res);
-        let gallery_id_selector = Selector::parse("h3[id=\"gallery_id\"]")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let parodies_selector = Selector::parse("a[href^=\"/parody/\"] > .name")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let characters_selector = Selector::parse("a[href^=\"/character/\"] > .name")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let languages_selector = Selector::parse("a[href^=\"/language/\"] > .name")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let categories_selector = Selector::parse("a[href^=\"/category/\"] > .name")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let pages_selector = Selector::parse("a[href^=\"/search/?q=pages\"] > .name")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let thumbnail_selector = Selector::parse("#cover > a > img")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let title_selector = Selector::parse("h1.title > .pretty")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let author_selector = Selector::parse("a[href^=\"/artist/\"] > .name")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let genre_selector = Selector::parse("a[href^=\"/tag/\"] > .name")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
+        let gallery_id_selector = parse_selector("h3[id=\"gallery_id\"]")?;
+        let parodies_selector = parse_selector("a[href^=\"/parody/\"] > .name")?;
+        let characters_selector = parse_selector("a[href^=\"/character/\"] > .name")?;
+        let languages_selector = parse_selector("a[href^=\"/language/\"] > .name")?;
+        let categories_selector = parse_selector("a[href^=\"/category/\"] > .name")?;
+        let pages_selector = parse_selector("a[href^=\"/search/?q=pages\"] > .name")?;
+        let thumbnail_selector = parse_selector("#cover > a > img")?;
+        let title_selector = parse_selector("h1.title > .pretty")?;
+        let author_selector = parse_selector("a[href^=\"/artist/\"] > .name")?;
+        let genre_selector = parse_selector("a[href^=\"/tag/\"] > .name")?;
 
         let mut description = "".to_string();
         if let Some(gallery_id) = document.select(&gallery_id_selector).next().map(|el| {
@@ -389,14 +387,14 @@ impl Extension for NHentai {
             .flat_map(|el| el.value().attr("data-src"))
             .next()
             .map(|s| s.to_string())
-            .ok_or_else(|| anyhow!("cover not found"))?;
+            .ok_or_else(|| SourceError::field("#cover > a > img", "cover_url"))?;
 
         let title = document
             .select(&title_selector)
             .flat_map(|el| el.text())
             .next()
             .map(|s| s.to_string())
-            .ok_or_else(|| anyhow!("title not found"))?;
+            .ok_or_else(|| SourceError::field("a > .caption", "title"))?;
 
         let author: Vec<String> = document
             .select(&author_selector)
@@ -433,10 +431,8 @@ impl Extension for NHentai {
             .into_string()?;
 
         let document = Html::parse_document(&res);
-        let scanlator_selector = Selector::parse("a[href^=\"/group/\"] > .name")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
-        let uploaded_selector = Selector::parse(".tags > time")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
+        let scanlator_selector = parse_selector("a[href^=\"/group/\"] > .name")?;
+        let uploaded_selector = parse_selector(".tags > time")?;
         let scanlator = document
             .select(&scanlator_selector)
             .flat_map(|el| el.text())
@@ -473,8 +469,7 @@ impl Extension for NHentai {
             .into_string()?;
 
         let document = Html::parse_document(&res);
-        let page_selector = Selector::parse(".thumb-container > .gallerythumb > img")
-            .map_err(|e| anyhow!("failed to parse selector: {e:?}"))?;
+        let page_selector = parse_selector(".thumb-container > .gallerythumb > img")?;
 
         let mut pages = vec![];
         let re = Regex::new(r"^https:\/\/t(\d*)\..+\/(\d+)\/(\d+)t.(.+)$")?;
@@ -493,8 +488,8 @@ impl Extension for NHentai {
         Ok(pages)
     }
 
-    fn headers(&self) -> std::collections::HashMap<String, String> {
-        std::collections::HashMap::new()
+    fn headers(&self) -> HashMap<String, String> {
+        image_headers(&self.client)
     }
 
     fn filter_list(&self) -> Vec<Input> {
@@ -525,6 +520,9 @@ mod test {
         ];
 
         let mut nhentai: NHentai = NHentai::default();
+        nhentai.client = nhentai
+            .client
+            .with_fixtures(networking::fixtures_dir!("nhentai"));
         
         nhentai.set_preferences(preferences).unwrap();
 
@@ -541,8 +539,6 @@ mod test {
 
     #[test]
     fn test_get_latest_manga() {
-        std::thread::sleep(std::time::Duration::from_secs(1));
-
         let nhentai: NHentai = create_test_instance();
 
         let res = nhentai.get_latest_manga(1).unwrap();
@@ -551,8 +547,6 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        std::thread::sleep(std::time::Duration::from_secs(2));
-
         let nhentai: NHentai = create_test_instance();
 
         let res = nhentai
@@ -563,8 +557,6 @@ mod test {
 
     #[test]
     fn test_search_manga_filter() {
-        std::thread::sleep(std::time::Duration::from_secs(3));
-
         let nhentai: NHentai = create_test_instance();
 
         let mut filters = nhentai.filter_list();
@@ -598,8 +590,6 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        std::thread::sleep(std::time::Duration::from_secs(1));
-
         let nhentai: NHentai = create_test_instance();
 
         let res = nhentai.get_chapters("/g/385965".to_string()).unwrap();
@@ -608,8 +598,6 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        std::thread::sleep(std::time::Duration::from_secs(2));
-
         let nhentai: NHentai = create_test_instance();
 
         let res = nhentai.get_pages("/g/385965".to_string()).unwrap();
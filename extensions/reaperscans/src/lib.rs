@@ -1,119 +1,51 @@
-use anyhow::bail;
-use madara::{
-    get_chapters, get_latest_manga, get_manga_detail, get_pages, get_popular_manga, search_manga,
-};
-use tanoshi_lib::prelude::{Extension, Input, Lang, PluginRegistrar, SourceInfo};
-use lazy_static::lazy_static;
-use networking::{Agent, build_ureq_agent};
-use std::env;
+use madara::{MadaraConfig, MadaraSource};
+use tanoshi_lib::prelude::{Lang, PluginRegistrar, SourceInfo};
 
 tanoshi_lib::export_plugin!(register);
 
 fn register(registrar: &mut dyn PluginRegistrar) {
-    registrar.register_function(Box::new(ReaperScans::default()));
-}
-
-lazy_static! {
-    static ref PREFERENCES: Vec<Input> = vec![];
+    registrar.register_function(Box::new(ReaperScans::new(config())));
 }
 
 const ID: i64 = 15;
 const NAME: &str = "ReaperScans";
 const URL: &str = "https://reaperscans.com";
 
-pub struct ReaperScans {
-    preferences: Vec<Input>,
-    client: Agent,
-}
-
-impl Default for ReaperScans {
-    fn default() -> Self {
-        Self {
-            preferences: PREFERENCES.clone(),
-            client: build_ureq_agent(None, None),
-        }
-    }
-}
-
-impl Extension for ReaperScans {
-    fn set_preferences(
-        &mut self,
-        preferences: Vec<Input>,
-    ) -> anyhow::Result<()> {
-        for input in preferences {
-            for pref in self.preferences.iter_mut() {
-                if input.eq(pref) {
-                    *pref = input.clone();
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    fn get_preferences(&self) -> anyhow::Result<Vec<Input>> {
-        Ok(self.preferences.clone())
-    }
-
-    fn get_source_info(&self) -> SourceInfo {
-        SourceInfo {
-            id: ID,
-            name: NAME.to_string(),
-            url: URL.to_string(),
-            version: env!("CARGO_PKG_VERSION"),
-            icon: "https://i.imgur.com/WX3l13S.png",
-            languages: Lang::Multi(vec![
-                "en".to_string(),
-                "pt-BR".to_string(),
-                "fr".to_string(),
-                "id".to_string(),
-                "tr".to_string(),
-            ]),
-            nsfw: false,
-        }
-    }
-
-    fn get_popular_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_popular_manga(URL, ID, page, &self.client)
-    }
-
-    fn get_latest_manga(&self, page: i64) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        get_latest_manga(URL, ID, page, &self.client)
-    }
-
-    fn search_manga(
-        &self,
-        page: i64,
-        query: Option<String>,
-        _: Option<Vec<Input>>,
-    ) -> anyhow::Result<Vec<tanoshi_lib::prelude::MangaInfo>> {
-        if let Some(query) = query {
-            search_manga(URL, ID, page, &query, false, &self.client)
-        } else {
-            bail!("query can not be empty")
-        }
-    }
-
-    fn get_manga_detail(&self, path: String) -> anyhow::Result<tanoshi_lib::prelude::MangaInfo> {
-        get_manga_detail(URL, &path, ID, &self.client)
-    }
-
-    fn get_chapters(&self, path: String) -> anyhow::Result<Vec<tanoshi_lib::prelude::ChapterInfo>> {
-        get_chapters(URL, &path, ID, Option::from("p.chapter-manhwa-title"), &self.client)
-    }
-
-    fn get_pages(&self, path: String) -> anyhow::Result<Vec<String>> {
-        get_pages(URL, &path, &self.client)
-    }
+pub type ReaperScans = MadaraSource;
+
+pub fn config() -> MadaraConfig {
+    MadaraConfig::new(SourceInfo {
+        id: ID,
+        name: NAME.to_string(),
+        url: URL.to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        icon: "https://i.imgur.com/WX3l13S.png",
+        languages: Lang::Single("en".to_string()),
+        nsfw: false,
+    })
+    // the other languages are sites of their own, on other domains
+    .language("en", "")
+    .chapter_name_selector("p.chapter-manhwa-title")
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use tanoshi_lib::prelude::Extension;
+
+    fn create_test_instance() -> ReaperScans {
+        let instance = ReaperScans::new(config());
+        let client = instance
+            .client()
+            .clone()
+            .with_fixtures(networking::fixtures_dir!("reaperscans"));
+
+        instance.with_client(client)
+    }
 
     #[test]
     fn test_get_latest_manga() {
-        let ReaperScans = ReaperScans::default();
+        let ReaperScans = create_test_instance();
 
         let res1 = ReaperScans.get_latest_manga(1).unwrap();
         assert!(!res1.is_empty());
@@ -130,7 +62,7 @@ mod test {
 
     #[test]
     fn test_get_popular_manga() {
-        let ReaperScans = ReaperScans::default();
+        let ReaperScans = create_test_instance();
 
         let res = ReaperScans.get_popular_manga(1).unwrap();
         assert!(!res.is_empty());
@@ -138,7 +70,7 @@ mod test {
 
     #[test]
     fn test_search_manga() {
-        let ReaperScans = ReaperScans::default();
+        let ReaperScans = create_test_instance();
 
         let res = ReaperScans
             .search_manga(1, Some("the+only".to_string()), None)
@@ -149,7 +81,7 @@ mod test {
 
     #[test]
     fn test_get_manga_detail() {
-        let ReaperScans = ReaperScans::default();
+        let ReaperScans = create_test_instance();
 
         let res = ReaperScans
             .get_manga_detail("/series/kill-the-dragon/".to_string())
@@ -160,7 +92,7 @@ mod test {
 
     #[test]
     fn test_get_chapters() {
-        let ReaperScans = ReaperScans::default();
+        let ReaperScans = create_test_instance();
 
         let res = ReaperScans
             .get_chapters("/series/kill-the-dragon/".to_string())
@@ -172,7 +104,7 @@ mod test {
 
     #[test]
     fn test_get_pages() {
-        let ReaperScans = ReaperScans::default();
+        let ReaperScans = create_test_instance();
 
         let res = ReaperScans
             .get_pages("/series/kill-the-dragon/chapter-4/".to_string())